@@ -0,0 +1,91 @@
+//! Measures commands per second against a fresh `iris server` with a growing number of connections.
+//!
+//! Run with `cargo bench -p iris --bench throughput`. Every connection sends `SET`/`GET` pairs on
+//! its own keys back to back for a few seconds (`IRIS_BENCH_SECS`, default 3).
+
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+
+const CONNECTIONS: [usize; 6] = [1, 2, 4, 8, 16, 32];
+
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn main() {
+    let secs = env::var("IRIS_BENCH_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(3);
+
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let _server = Server(
+        Command::new(env!("CARGO_BIN_EXE_iris"))
+            .args(["server", "-p", &port.to_string()])
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("failed to spawn iris"),
+    );
+
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    println!("{:>11}  {:>12}  {:>14}", "connections", "commands/s", "per connection");
+
+    for connections in CONNECTIONS {
+        let total = run(port, connections, Duration::from_secs(secs));
+        let per_second = total as f64 / secs as f64;
+
+        println!("{connections:>11}  {per_second:>12.0}  {:>14.0}", per_second / connections as f64);
+    }
+}
+
+/// Returns how many commands all connections got through in `duration`.
+fn run(port: u16, connections: usize, duration: Duration) -> usize {
+    let barrier = Arc::new(Barrier::new(connections));
+
+    let workers: Vec<_> = (0..connections)
+        .map(|worker| {
+            let barrier = Arc::clone(&barrier);
+
+            thread::spawn(move || {
+                let mut writer = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                writer.set_nodelay(true).unwrap();
+                let mut reader = BufReader::new(writer.try_clone().unwrap());
+                let mut line = String::new();
+                let mut commands = 0;
+
+                barrier.wait();
+                let start = Instant::now();
+
+                while start.elapsed() < duration {
+                    let id = format!("bench:{worker}:{}", commands % 1000);
+
+                    for command in [format!("SET {id} value"), format!("GET {id}")] {
+                        writeln!(writer, "{command}").unwrap();
+                        line.clear();
+                        reader.read_line(&mut line).unwrap();
+                        commands += 1;
+                    }
+                }
+
+                commands
+            })
+        })
+        .collect();
+
+    workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+}
@@ -0,0 +1,185 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, RwLock},
+};
+
+use crate::{command::Command, pattern};
+
+pub const NOAUTH: &str = "NOAUTH Authentication required";
+pub const DEFAULT_USER: &str = "default";
+
+/// A user and the commands and key patterns it is allowed to use.
+/// Rules are glob patterns, so `*` allows every command or every key.
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    #[serde(skip)]
+    pub name: String,
+    pub password: Option<String>,
+    #[serde(default = "everything")]
+    pub commands: Vec<String>,
+    #[serde(default = "everything")]
+    pub keys: Vec<String>,
+}
+
+fn everything() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+impl User {
+    pub fn can_run(&self, name: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|rule| rule == "*" || rule.eq_ignore_ascii_case(name))
+    }
+
+    pub fn can_access(&self, id: &str) -> bool {
+        self.keys.iter().any(|rule| pattern::matches(rule, id))
+    }
+
+    /// Whether the key rules let every key through, so per-key checks can be skipped.
+    pub fn can_access_everything(&self) -> bool {
+        self.keys.iter().any(|rule| rule == "*")
+    }
+
+    pub fn authorize(&self, command: &Command) -> Result<(), String> {
+        // Every user may ask who they are.
+        if let Command::AclWhoami {  } = command {
+            return Ok(());
+        }
+
+        if !self.can_run(command.name()) {
+            return Err(format!("NOPERM this user has no permissions to run the {:?} command", command.name()));
+        }
+
+        if let Some(id) = command.ids().into_iter().find(|id| !self.can_access(id)) {
+            return Err(format!("NOPERM this user has no permissions to access the {id:?} key"));
+        }
+
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "user {} commands={} keys={}",
+            self.name,
+            self.commands.join(","),
+            self.keys.join(",")
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct AclFile {
+    #[serde(default)]
+    users: HashMap<String, User>,
+}
+
+/// Reads users from a toml file of `[users.<name>]` tables.
+pub fn load_acl_file(path: &str) -> Result<Vec<User>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read the acl file: {err}"))?;
+    let file: AclFile = toml::from_str(&contents).map_err(|err| format!("Invalid acl file: {err}"))?;
+
+    Ok(file
+        .users
+        .into_iter()
+        .map(|(name, user)| User { name, ..user })
+        .collect())
+}
+
+/// The users connections can `AUTH` as.
+///
+/// Without an acl file there is only the `default` user, which has every permission and is
+/// protected by `--requirepass` if given. With an acl file, connections start unauthenticated
+/// unless the file defines a `default` user without a password.
+pub struct Auth {
+    users: RwLock<HashMap<String, Arc<User>>>,
+}
+
+impl Auth {
+    pub fn new(password: Option<String>, users: Vec<User>) -> Auth {
+        let mut registry: HashMap<String, Arc<User>> = HashMap::new();
+
+        if password.is_some() || users.is_empty() {
+            let default = User {
+                name: DEFAULT_USER.to_string(),
+                password,
+                commands: everything(),
+                keys: everything(),
+            };
+
+            registry.insert(default.name.clone(), Arc::new(default));
+        }
+
+        for user in users {
+            registry.insert(user.name.clone(), Arc::new(user));
+        }
+
+        Auth { users: RwLock::new(registry) }
+    }
+
+    /// The user a fresh connection is logged in as, if it doesn't need to `AUTH`.
+    pub fn default_user(&self) -> Option<Arc<User>> {
+        self.users
+            .read()
+            .unwrap()
+            .get(DEFAULT_USER)
+            .filter(|user| user.password.is_none())
+            .cloned()
+    }
+
+    pub fn authenticate(&self, username: Option<&str>, password: &str) -> Result<Arc<User>, String> {
+        let users = self.users.read().unwrap();
+
+        if users.len() == 1 && users.get(DEFAULT_USER).is_some_and(|user| user.password.is_none()) {
+            return Err("AUTH called without any password configured".to_string());
+        }
+
+        let user = users
+            .get(username.unwrap_or(DEFAULT_USER))
+            .filter(|user| match &user.password {
+                Some(expected) => constant_time_eq(expected.as_bytes(), password.as_bytes()),
+                None => true,
+            });
+
+        match user {
+            Some(user) => Ok(Arc::clone(user)),
+            None if username.is_some() => Err("WRONGPASS invalid username-password pair".to_string()),
+            None => Err("WRONGPASS invalid password".to_string()),
+        }
+    }
+
+    /// Changes the password of the `default` user, creating it with every permission if the acl file didn't define it.
+    pub fn set_password(&self, password: Option<String>) {
+        let mut users = self.users.write().unwrap();
+
+        let default = match users.get(DEFAULT_USER) {
+            Some(user) => User { password, ..User::clone(user) },
+            None => User {
+                name: DEFAULT_USER.to_string(),
+                password,
+                commands: everything(),
+                keys: everything(),
+            },
+        };
+
+        users.insert(default.name.clone(), Arc::new(default));
+    }
+
+    pub fn users(&self) -> Vec<Arc<User>> {
+        let mut users: Vec<Arc<User>> = self.users.read().unwrap().values().cloned().collect();
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+
+        users
+    }
+}
+
+/// Compares without short-circuiting so response times don't leak how much of the password matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use crate::value;
+
+/// The bit at `offset`, counting from the most significant bit of the first byte. Past the end is `0`.
+pub fn get(bytes: &[u8], offset: usize) -> u8 {
+    match bytes.get(offset / 8) {
+        Some(byte) => (byte >> (7 - offset % 8)) & 1,
+        None => 0,
+    }
+}
+
+/// Sets the bit at `offset`, growing the bitmap with zero bytes as needed. Returns the bit it replaced.
+pub fn set(bytes: &mut Vec<u8>, offset: usize, bit: u8) -> u8 {
+    let old = get(bytes, offset);
+
+    if bytes.len() <= offset / 8 {
+        bytes.resize(offset / 8 + 1, 0);
+    }
+
+    let mask = 1 << (7 - offset % 8);
+    match bit {
+        0 => bytes[offset / 8] &= !mask,
+        _ => bytes[offset / 8] |= mask,
+    }
+
+    old
+}
+
+/// What the range of `BITCOUNT` counts in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unit {
+    #[default]
+    Byte,
+    Bit,
+}
+
+/// Counts the set bits, of all `bytes` or an inclusive range of them like `GETRANGE` takes it.
+pub fn count(bytes: &[u8], range: Option<(i32, i32, Unit)>) -> usize {
+    let ones = |bytes: &[u8]| bytes.iter().map(|byte| byte.count_ones() as usize).sum();
+
+    match range {
+        None => ones(bytes),
+        Some((start, end, Unit::Byte)) => match value::range(bytes.len(), start, end) {
+            Some((start, end)) => ones(&bytes[start..=end]),
+            None => 0,
+        },
+        Some((start, end, Unit::Bit)) => match value::range(bytes.len() * 8, start, end) {
+            Some((start, end)) => (start..=end).filter(|offset| get(bytes, *offset) == 1).count(),
+            None => 0,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(op: &str) -> Result<Op, String> {
+        match op {
+            "AND" => Ok(Op::And),
+            "OR" => Ok(Op::Or),
+            "XOR" => Ok(Op::Xor),
+            "NOT" => Ok(Op::Not),
+            _ => Err(format!("Invalid BITOP operation {op:?}, valid values: ('AND', 'OR', 'XOR', 'NOT')")),
+        }
+    }
+}
+
+/// Combines bitmaps byte by byte. Shorter ones count as padded with zero bytes, so the result is as long
+/// as the longest. `NOT` only takes one.
+pub fn op(op: Op, sources: &[&[u8]]) -> Vec<u8> {
+    let len = sources.iter().map(|bytes| bytes.len()).max().unwrap_or(0);
+    let byte = |bytes: &[u8], index: usize| bytes.get(index).copied().unwrap_or(0);
+
+    (0..len)
+        .map(|index| {
+            let mut bytes = sources.iter().map(|bytes| byte(bytes, index));
+
+            match op {
+                Op::And => bytes.fold(0xff, |result, byte| result & byte),
+                Op::Or => bytes.fold(0, |result, byte| result | byte),
+                Op::Xor => bytes.fold(0, |result, byte| result ^ byte),
+                Op::Not => !bytes.next().unwrap_or(0),
+            }
+        })
+        .collect()
+}
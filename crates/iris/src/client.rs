@@ -1,20 +1,44 @@
 use indoc::{indoc, printdoc};
-use std::process;
+use std::{
+    net::{IpAddr, SocketAddr},
+    process,
+};
 use tokio::{
     io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
+    net::{TcpStream, UnixStream},
 };
+use tokio_rustls::{rustls::pki_types::ServerName, TlsConnector};
+
+use crate::tls::Stream;
+
+/// Where the repl connects to.
+pub enum Target {
+    Tcp { host: String, port: u16, tls: Option<TlsConnector> },
+    Unix { path: String },
+}
 
-pub async fn start(addr: &str) {
-    let mut stream = match TcpStream::connect(addr).await {
+pub async fn start(target: Target, username: Option<String>, password: Option<String>, database: Option<usize>) {
+    let mut stream = match connect(&target).await {
         Ok(stream) => stream,
         Err(err) => {
-            println!("Failed to connect: {err}");
+            println!("{err}");
             process::exit(1);
         }
     };
 
-    let local_addr = stream.peer_addr().unwrap();
+    if let Some(password) = password {
+        if let Err(err) = authenticate(&mut stream, username.as_deref(), &password).await {
+            println!("Failed to authenticate: {err}");
+            process::exit(1);
+        }
+    }
+
+    if let Some(database) = database {
+        if let Err(err) = setup(&mut stream, format!("SELECT {database}\n")).await {
+            println!("Failed to select the database: {err}");
+            process::exit(1);
+        }
+    }
 
     println!(
         indoc! {"
@@ -26,15 +50,24 @@ pub async fn start(addr: &str) {
             ▄▄█▄▄   █     ▄▄█▄▄  ▀▄▄▄▀
 
             Client is connected.
-            • version:  {}
-            • host:     http://{}
-            • port:     {}
-        "},
-        env!("CARGO_PKG_VERSION"),
-        local_addr,
-        local_addr.port()
+            • version:  {}"},
+        env!("CARGO_PKG_VERSION")
     );
 
+    match &target {
+        Target::Tcp { host, port, .. } => {
+            println!("• host:     http://{}", socket_addr(host, *port));
+            println!("• port:     {port}");
+        }
+        Target::Unix { path } => println!("• socket:   {path}"),
+    }
+
+    if let Some(database) = database {
+        println!("• database: {database}");
+    }
+
+    println!();
+
     loop {
         let line = match prompt().await {
             Ok(line) => line,
@@ -74,6 +107,65 @@ pub async fn start(addr: &str) {
                  - SET <expr> <data>  : sets a value on a key.
                  - DEL <expr>         : deletes a value on a key.
                  - PING               : ping the server.
+                 - AUTH [user] <pass> : authenticate the connection.
+                 - ACL WHOAMI         : show the authenticated user.
+                 - ACL LIST           : list users and their permissions.
+                 - CONFIG GET <glob>  : show settings matching a pattern.
+                 - CONFIG SET <n> <v> : change a setting on the running server.
+                 - CONFIG RELOAD      : re-read the config file.
+                 - SHUTDOWN [NOSAVE]  : stop the server gracefully.
+                 - SELECT <index>     : switch the connection to another database.
+                 - FLUSHDB            : delete every key of the current database.
+                 - FLUSHALL           : delete every key of every database.
+                 - LST <expr> IN <ns> : list keys of a namespace, CNT works too.
+                 - NS LIST            : list namespaces and their key counts.
+                 - NS DROP <ns>       : delete every key of a namespace.
+                 - EXISTS <id>...     : count how many ids exist.
+                 - RENAME <a> <b>     : move a value, RENAMENX won't replace.
+                 - COPY <a> <b>       : copy a value, add REPLACE to overwrite.
+                 - TYPE <id>          : show the type of a value.
+                 - KEYS <expr>        : list ids without values, takes a glob.
+                 - APPEND <id> <data> : append to a value.
+                 - STRLEN <id>        : length of a value in bytes.
+                 - GETRANGE <id> <r>  : part of a value, range (0..5).
+                 - SETRANGE <id> <o> <data> : overwrite part of a value.
+                 - SETBIT <id> <o> <b>: set a bit of a bitmap.
+                 - GETBIT <id> <o>    : get a bit of a bitmap.
+                 - BITCOUNT <id> [r]  : count set bits, add BIT to count the range in bits.
+                 - BITOP <op> <d> <id>: AND, OR, XOR or NOT bitmaps into a destination.
+                 - PFADD <id> <el>... : add elements to a hyperloglog.
+                 - PFCOUNT <id>...    : estimate the distinct elements added.
+                 - PFMERGE <d> <id>...: merge hyperloglogs into a destination.
+                 - XADD <id> * <f> <v>: append an entry to a stream.
+                 - XRANGE <id> - +    : list entries of a stream, add COUNT <n> to limit.
+                 - XLEN <id>          : count entries of a stream.
+                 - XTRIM <id> MAXLEN <n> : keep the newest entries.
+                 - XGROUP CREATE <id> <group> <$|0> : create a consumer group.
+                 - XREADGROUP GROUP <g> <c> STREAMS <id> > : read as a consumer.
+                 - XACK <id> <group> <entry>... : acknowledge entries.
+                 - XPENDING <id> <group> : list unacknowledged entries.
+                 - GEOADD <id> <lon> <lat> <member> : add a position.
+                 - GEOPOS <id> <member>... : show positions.
+                 - GEODIST <id> <a> <b> [unit] : distance between members.
+                 - GEOSEARCH <id> FROMLONLAT <lon> <lat> BYRADIUS <r> <unit> : find members nearby.
+                 - JSET <id> <path> <json> : set JSON at a path, $ for the document.
+                 - JGET <id> [path]   : show JSON at a path.
+                 - JDEL <id> [path]   : remove JSON at a path.
+                 - JARRAPPEND <id> <path> <json>... : append to an array.
+                 - JNUMINCRBY <id> <path> <n> : add to a number.
+                 - INDEX CREATE <name> ON <pattern> FIELD <path> : index keys by a JSON value.
+                 - INDEX CREATE <name> ON <pattern> TEXT : index the words of keys.
+                 - INDEX CREATE <name> ON <pattern> VECTOR <dims> : index vectors of keys.
+                 - INDEX DROP <name>  : remove an index.
+                 - INDEX LIST         : list indexes.
+                 - FIND <name> <value|min..max> : find ids by indexed value.
+                 - SEARCH <name> <query> : find ids by words, ranked by relevance.
+                 - VADD <id> <f32>... : store a vector.
+                 - VGET <id>          : show a vector.
+                 - VSEARCH <name> <k> <f32>... : find the nearest vectors.
+                 - EVAL {{<script>}} <numkeys> <key>... <arg>... : run a script atomically.
+                 - SCRIPT LOAD {{<script>}} : compile a script for EVALSHA.
+                 - EVALSHA <sha> <numkeys> <key>... <arg>... : run a loaded script.
                  - help               : show this message.
                  - clear              : clear prompt.
                  - exit               : exit repl.
@@ -117,6 +209,67 @@ pub async fn start(addr: &str) {
     }
 }
 
+async fn connect(target: &Target) -> Result<Box<dyn Stream>, String> {
+    match target {
+        Target::Tcp { host, port, tls } => {
+            let socket = TcpStream::connect((host.as_str(), *port))
+                .await
+                .map_err(|err| format!("Failed to connect: {err}"))?;
+
+            match tls {
+                Some(connector) => {
+                    let server_name = ServerName::try_from(host.to_string())
+                        .map_err(|err| format!("Invalid tls server name: {err}"))?;
+
+                    let stream = connector
+                        .connect(server_name, socket)
+                        .await
+                        .map_err(|err| format!("Failed the tls handshake: {err}"))?;
+
+                    Ok(Box::new(stream))
+                }
+                None => Ok(Box::new(socket)),
+            }
+        }
+        Target::Unix { path } => {
+            let socket = UnixStream::connect(path)
+                .await
+                .map_err(|err| format!("Failed to connect: {err}"))?;
+
+            Ok(Box::new(socket))
+        }
+    }
+}
+
+async fn authenticate(stream: &mut Box<dyn Stream>, username: Option<&str>, password: &str) -> Result<(), String> {
+    let command = match username {
+        Some(username) => format!("AUTH {username} {password}\n"),
+        None => format!("AUTH {password}\n"),
+    };
+
+    setup(stream, command).await
+}
+
+/// Sends a command before the repl starts, failing on anything but an `ok`.
+async fn setup(stream: &mut Box<dyn Stream>, command: String) -> Result<(), String> {
+    stream
+        .write_all(command.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send: {e}"))?;
+
+    let mut buffer = String::new();
+    BufReader::new(stream)
+        .read_line(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read: {e}"))?;
+
+    match buffer.trim().split_once(' ') {
+        Some(("ok", _)) => Ok(()),
+        Some((_, err)) => Err(err.to_string()),
+        None => Err("Connection closed".to_string()),
+    }
+}
+
 async fn prompt() -> Result<String, String> {
     let mut stdin = BufReader::new(tokio::io::stdin());
     let mut stdout = tokio::io::stdout();
@@ -140,3 +293,11 @@ async fn prompt() -> Result<String, String> {
 
     Ok(line)
 }
+
+/// Formats host and port as an address, bracketing ipv6 hosts.
+fn socket_addr(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) => format!("{host}:{port}"),
+    }
+}
@@ -1,3 +1,13 @@
+use crate::{
+    bitmap::{Op, Unit},
+    geo::{self, Point, Shape},
+    index::{Field, Query},
+    json::{self, Path},
+    script, search,
+    vector::{Metric, Vector},
+    stream::{Fields, StreamId},
+};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expr {
     ID(String),
@@ -5,27 +15,318 @@ pub enum Expr {
     Range(i32, i32)
 }
 
+/// Where `GEOSEARCH` searches around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Center {
+    Member(String),
+    Point(Point),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchOptions {
+    pub descending: bool,
+    pub count: Option<usize>,
+    pub with_dist: bool,
+    pub with_coord: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     Ping {  },
     Get { id: String },
-    List { expr: Expr },
-    Count { expr: Expr },
+    List { expr: Expr, namespace: Option<String> },
+    Count { expr: Expr, namespace: Option<String> },
     Set { id: String, data: String },
     Delete { expr: Expr },
+    Auth { username: Option<String>, password: String },
+    AclWhoami {  },
+    AclList {  },
+    ConfigGet { pattern: String },
+    ConfigSet { name: String, value: String },
+    ConfigReload {  },
+    Shutdown { save: bool },
+    Select { database: usize },
+    FlushDb {  },
+    FlushAll {  },
+    NamespaceList {  },
+    Exists { ids: Vec<String> },
+    /// `RENAME` with `replace`, `RENAMENX` without.
+    Rename { from: String, to: String, replace: bool },
+    Copy { from: String, to: String, replace: bool },
+    Type { id: String },
+    Keys { expr: Expr },
+    Append { id: String, data: String },
+    StrLen { id: String },
+    /// Byte offsets, inclusive. Negative ones count from the end.
+    GetRange { id: String, start: i32, end: i32 },
+    SetRange { id: String, offset: usize, data: String },
+    SetBit { id: String, offset: usize, bit: u8 },
+    GetBit { id: String, offset: usize },
+    /// Counts the whole value without a range.
+    BitCount { id: String, range: Option<(i32, i32, Unit)> },
+    BitOp { op: Op, destination: String, sources: Vec<String> },
+    PfAdd { id: String, elements: Vec<String> },
+    /// Estimates the union of every id.
+    PfCount { ids: Vec<String> },
+    PfMerge { destination: String, sources: Vec<String> },
+    /// `entry` is `None` for `*`, an id from the clock.
+    XAdd { id: String, entry: Option<StreamId>, fields: Fields, maxlen: Option<usize> },
+    XRange { id: String, start: StreamId, end: StreamId, count: Option<usize> },
+    XLen { id: String },
+    XTrim { id: String, maxlen: usize },
+    /// `start` is `None` for `$`, only entries added after the group.
+    XGroupCreate { id: String, group: String, start: Option<StreamId>, mkstream: bool },
+    /// `after` is `None` for `>`, entries no one in the group got yet.
+    XReadGroup { id: String, group: String, consumer: String, after: Option<StreamId>, count: Option<usize> },
+    XAck { id: String, group: String, entries: Vec<StreamId> },
+    XPending { id: String, group: String },
+    GeoAdd { id: String, members: Vec<(Point, String)> },
+    GeoPos { id: String, members: Vec<String> },
+    GeoDist { id: String, from: String, to: String, unit: geo::Unit },
+    GeoSearch { id: String, center: Center, shape: Shape, unit: geo::Unit, options: SearchOptions },
+    JsonSet { id: String, path: Path, value: serde_json::Value },
+    JsonGet { id: String, path: Path },
+    JsonDelete { id: String, path: Path },
+    JsonArrAppend { id: String, path: Path, values: Vec<serde_json::Value> },
+    JsonNumIncrBy { id: String, path: Path, by: serde_json::Number },
+    IndexCreate { name: String, pattern: String, field: Field },
+    IndexDrop { name: String },
+    IndexList {  },
+    Find { name: String, query: Query },
+    Search { name: String, query: search::Query },
+    VAdd { id: String, vector: Vector },
+    VGet { id: String },
+    VSearch { name: String, k: usize, vector: Vector, exact: bool, with_dist: bool },
+    /// `script` keeps its braces, it's what the sha is taken of.
+    Eval { script: String, keys: Vec<String>, args: Vec<String> },
+    EvalSha { sha: String, keys: Vec<String>, args: Vec<String> },
+    ScriptLoad { script: String },
+    NamespaceDrop { namespace: String },
+    Subscribe { pattern: String },
+    Unsubscribe { pattern: String },
     Invalid { reason: String },
 }
 
+impl Command {
+    /// The keyword the command is sent with, which is what ACL rules match against.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Ping {  } => "PING",
+            Command::Get { .. } => "GET",
+            Command::List { .. } => "LST",
+            Command::Count { .. } => "CNT",
+            Command::Set { .. } => "SET",
+            Command::Delete { .. } => "DEL",
+            Command::Auth { .. } => "AUTH",
+            Command::AclWhoami {  } | Command::AclList {  } => "ACL",
+            Command::ConfigGet { .. } | Command::ConfigSet { .. } | Command::ConfigReload {  } => "CONFIG",
+            Command::Shutdown { .. } => "SHUTDOWN",
+            Command::Select { .. } => "SELECT",
+            Command::FlushDb {  } => "FLUSHDB",
+            Command::FlushAll {  } => "FLUSHALL",
+            Command::NamespaceList {  } | Command::NamespaceDrop { .. } => "NS",
+            Command::Exists { .. } => "EXISTS",
+            Command::Rename { replace: true, .. } => "RENAME",
+            Command::Rename { replace: false, .. } => "RENAMENX",
+            Command::Copy { .. } => "COPY",
+            Command::Type { .. } => "TYPE",
+            Command::Keys { .. } => "KEYS",
+            Command::Append { .. } => "APPEND",
+            Command::StrLen { .. } => "STRLEN",
+            Command::GetRange { .. } => "GETRANGE",
+            Command::SetRange { .. } => "SETRANGE",
+            Command::SetBit { .. } => "SETBIT",
+            Command::GetBit { .. } => "GETBIT",
+            Command::BitCount { .. } => "BITCOUNT",
+            Command::BitOp { .. } => "BITOP",
+            Command::PfAdd { .. } => "PFADD",
+            Command::PfCount { .. } => "PFCOUNT",
+            Command::PfMerge { .. } => "PFMERGE",
+            Command::XAdd { .. } => "XADD",
+            Command::XRange { .. } => "XRANGE",
+            Command::XLen { .. } => "XLEN",
+            Command::XTrim { .. } => "XTRIM",
+            Command::XGroupCreate { .. } => "XGROUP",
+            Command::XReadGroup { .. } => "XREADGROUP",
+            Command::XAck { .. } => "XACK",
+            Command::XPending { .. } => "XPENDING",
+            Command::GeoAdd { .. } => "GEOADD",
+            Command::GeoPos { .. } => "GEOPOS",
+            Command::GeoDist { .. } => "GEODIST",
+            Command::GeoSearch { .. } => "GEOSEARCH",
+            Command::JsonSet { .. } => "JSET",
+            Command::JsonGet { .. } => "JGET",
+            Command::JsonDelete { .. } => "JDEL",
+            Command::JsonArrAppend { .. } => "JARRAPPEND",
+            Command::JsonNumIncrBy { .. } => "JNUMINCRBY",
+            Command::IndexCreate { .. } | Command::IndexDrop { .. } | Command::IndexList {  } => "INDEX",
+            Command::Find { .. } => "FIND",
+            Command::Search { .. } => "SEARCH",
+            Command::VAdd { .. } => "VADD",
+            Command::VGet { .. } => "VGET",
+            Command::VSearch { .. } => "VSEARCH",
+            Command::Eval { .. } => "EVAL",
+            Command::EvalSha { .. } => "EVALSHA",
+            Command::ScriptLoad { .. } => "SCRIPT",
+            Command::Subscribe { .. } => "SUB",
+            Command::Unsubscribe { .. } => "UNSUB",
+            Command::Invalid { .. } => "",
+        }
+    }
+
+    /// Whether the command reads or writes keys, which a running script has to keep out.
+    pub fn touches_keys(&self) -> bool {
+        !matches!(
+            self,
+            Command::Ping {  }
+                | Command::Auth { .. }
+                | Command::AclWhoami {  }
+                | Command::AclList {  }
+                | Command::ConfigGet { .. }
+                | Command::ConfigSet { .. }
+                | Command::ConfigReload {  }
+                | Command::Shutdown { .. }
+                | Command::Select { .. }
+                | Command::ScriptLoad { .. }
+                | Command::Subscribe { .. }
+                | Command::Unsubscribe { .. }
+                | Command::Invalid { .. }
+        )
+    }
+
+    /// The ids the command names directly. Numbers and ranges aren't ids,
+    /// those only ever see the keys the connection is allowed to access.
+    pub fn ids(&self) -> Vec<&str> {
+        match self {
+            Command::Get { id }
+            | Command::Set { id, .. }
+            | Command::Type { id }
+            | Command::Append { id, .. }
+            | Command::StrLen { id }
+            | Command::GetRange { id, .. }
+            | Command::SetRange { id, .. }
+            | Command::SetBit { id, .. }
+            | Command::GetBit { id, .. }
+            | Command::BitCount { id, .. }
+            | Command::PfAdd { id, .. }
+            | Command::XAdd { id, .. }
+            | Command::XRange { id, .. }
+            | Command::XLen { id }
+            | Command::XTrim { id, .. }
+            | Command::XGroupCreate { id, .. }
+            | Command::XReadGroup { id, .. }
+            | Command::XAck { id, .. }
+            | Command::XPending { id, .. }
+            | Command::GeoAdd { id, .. }
+            | Command::GeoPos { id, .. }
+            | Command::GeoDist { id, .. }
+            | Command::GeoSearch { id, .. }
+            | Command::JsonSet { id, .. }
+            | Command::JsonGet { id, .. }
+            | Command::JsonDelete { id, .. }
+            | Command::JsonArrAppend { id, .. }
+            | Command::JsonNumIncrBy { id, .. }
+            | Command::VAdd { id, .. }
+            | Command::VGet { id } => vec![id],
+            Command::BitOp { destination, sources, .. } | Command::PfMerge { destination, sources } => {
+                std::iter::once(destination).chain(sources).map(String::as_str).collect()
+            }
+            Command::Exists { ids } | Command::PfCount { ids } => ids.iter().map(String::as_str).collect(),
+            Command::Eval { keys, .. } | Command::EvalSha { keys, .. } => keys.iter().map(String::as_str).collect(),
+            Command::Rename { from, to, .. } | Command::Copy { from, to, .. } => vec![from, to],
+            Command::List { expr: Expr::ID(id), .. }
+            | Command::Count { expr: Expr::ID(id), .. }
+            | Command::Delete { expr: Expr::ID(id) } => vec![id],
+            _ => vec![],
+        }
+    }
+}
+
 pub fn parse_command(input: String) -> Command {
+    // Scripts hold spaces of their own, so they're split off before the rest.
+    if let Some(rest) = input.trim_start().strip_prefix("EVAL ") {
+        return match parse_eval(rest.trim_start()) {
+            Ok(command) => command,
+            Err(err) => Command::Invalid { reason: err },
+        };
+    }
+
+    if let Some(rest) = input.trim_start().strip_prefix("SCRIPT LOAD ") {
+        return match script::split("SCRIPT LOAD", rest.trim_start()) {
+            Ok((script, rest)) if rest.trim().is_empty() => Command::ScriptLoad { script: script.to_string() },
+            Ok(_) => Command::Invalid { reason: r#""SCRIPT LOAD" takes only a Script"#.to_string() },
+            Err(err) => Command::Invalid { reason: err },
+        };
+    }
+
     let parts: Vec<&str> = input.split_whitespace().collect();
+    parse_parts(&parts)
+}
 
-    match parts.as_slice() {
+/// Parses a command already split into its parts, like the args of a script's `call(...)`,
+/// which keep any whitespace of their own.
+pub fn parse_parts(parts: &[&str]) -> Command {
+    match parts {
         // Error Handling
         ["GET"] => Command::Invalid { reason: r#""GET" requires an ID"#.to_owned() },
         ["SET"] => Command::Invalid { reason: r#""SET" requires an ID"#.to_owned() },
         ["LST"] => Command::Invalid { reason: r#""LST" requires an Expression"#.to_owned() },
         ["CNT"] => Command::Invalid { reason: r#""LST" requires an Expression"#.to_owned() },
         ["DEL"] => Command::Invalid { reason: r#""LST" requires an Expression"#.to_owned() },
+        ["AUTH"] => Command::Invalid { reason: r#""AUTH" requires a Password"#.to_owned() },
+        ["ACL"] => Command::Invalid { reason: r#""ACL" requires a Subcommand ('WHOAMI', 'LIST')"#.to_owned() },
+        ["CONFIG"] => Command::Invalid { reason: r#""CONFIG" requires a Subcommand ('GET', 'SET', 'RELOAD')"#.to_owned() },
+        ["CONFIG", "GET"] => Command::Invalid { reason: r#""CONFIG GET" requires a Pattern"#.to_owned() },
+        ["CONFIG", "SET"] => Command::Invalid { reason: r#""CONFIG SET" requires a Name"#.to_owned() },
+        ["NS"] => Command::Invalid { reason: r#""NS" requires a Subcommand ('LIST', 'DROP')"#.to_owned() },
+        ["NS", "DROP"] => Command::Invalid { reason: r#""NS DROP" requires a Namespace"#.to_owned() },
+        ["EXISTS"] => Command::Invalid { reason: r#""EXISTS" requires an ID"#.to_owned() },
+        ["RENAME"] | ["RENAME", _] => Command::Invalid { reason: r#""RENAME" requires an ID and a new ID"#.to_owned() },
+        ["RENAMENX"] | ["RENAMENX", _] => Command::Invalid { reason: r#""RENAMENX" requires an ID and a new ID"#.to_owned() },
+        ["COPY"] | ["COPY", _] => Command::Invalid { reason: r#""COPY" requires an ID and a destination ID"#.to_owned() },
+        ["TYPE"] => Command::Invalid { reason: r#""TYPE" requires an ID"#.to_owned() },
+        ["KEYS"] => Command::Invalid { reason: r#""KEYS" requires an Expression"#.to_owned() },
+        ["APPEND"] | ["APPEND", _] => Command::Invalid { reason: r#""APPEND" requires an ID and a Data"#.to_owned() },
+        ["STRLEN"] => Command::Invalid { reason: r#""STRLEN" requires an ID"#.to_owned() },
+        ["GETRANGE"] | ["GETRANGE", _] => Command::Invalid { reason: r#""GETRANGE" requires an ID and a Range (0..5)"#.to_owned() },
+        ["SETRANGE"] | ["SETRANGE", _] | ["SETRANGE", _, _] => Command::Invalid { reason: r#""SETRANGE" requires an ID, an Offset and a Data"#.to_owned() },
+        ["SETBIT"] | ["SETBIT", _] | ["SETBIT", _, _] => Command::Invalid { reason: r#""SETBIT" requires an ID, an Offset and a Bit"#.to_owned() },
+        ["GETBIT"] | ["GETBIT", _] => Command::Invalid { reason: r#""GETBIT" requires an ID and an Offset"#.to_owned() },
+        ["BITCOUNT"] => Command::Invalid { reason: r#""BITCOUNT" requires an ID"#.to_owned() },
+        ["BITOP"] | ["BITOP", _] | ["BITOP", _, _] => Command::Invalid { reason: r#""BITOP" requires an Operation, a destination ID and source IDs"#.to_owned() },
+        ["PFADD"] => Command::Invalid { reason: r#""PFADD" requires an ID"#.to_owned() },
+        ["PFCOUNT"] => Command::Invalid { reason: r#""PFCOUNT" requires an ID"#.to_owned() },
+        ["PFMERGE"] => Command::Invalid { reason: r#""PFMERGE" requires a destination ID"#.to_owned() },
+        ["XADD"] | ["XADD", _] => Command::Invalid { reason: r#""XADD" requires an ID, an entry ID and fields"#.to_owned() },
+        ["XRANGE"] | ["XRANGE", _] | ["XRANGE", _, _] => Command::Invalid { reason: r#""XRANGE" requires an ID, a start and an end"#.to_owned() },
+        ["XLEN"] => Command::Invalid { reason: r#""XLEN" requires an ID"#.to_owned() },
+        ["XGROUP"] => Command::Invalid { reason: r#""XGROUP" requires a Subcommand ('CREATE')"#.to_owned() },
+        ["XREADGROUP"] => Command::Invalid { reason: r#""XREADGROUP" requires GROUP <group> <consumer>"#.to_owned() },
+        ["XACK"] | ["XACK", _] => Command::Invalid { reason: r#""XACK" requires an ID, a group and entry IDs"#.to_owned() },
+        ["XPENDING"] | ["XPENDING", _] => Command::Invalid { reason: r#""XPENDING" requires an ID and a group"#.to_owned() },
+        ["GEOADD"] | ["GEOADD", _] => Command::Invalid { reason: r#""GEOADD" requires an ID and longitude latitude member triples"#.to_owned() },
+        ["GEOPOS"] => Command::Invalid { reason: r#""GEOPOS" requires an ID"#.to_owned() },
+        ["GEODIST"] | ["GEODIST", _] | ["GEODIST", _, _] => Command::Invalid { reason: r#""GEODIST" requires an ID and two members"#.to_owned() },
+        ["GEOSEARCH"] => Command::Invalid { reason: r#""GEOSEARCH" requires an ID"#.to_owned() },
+        ["JSET"] | ["JSET", _] | ["JSET", _, _] => Command::Invalid { reason: r#""JSET" requires an ID, a Path and a Value"#.to_owned() },
+        ["JGET"] => Command::Invalid { reason: r#""JGET" requires an ID"#.to_owned() },
+        ["JDEL"] => Command::Invalid { reason: r#""JDEL" requires an ID"#.to_owned() },
+        ["JARRAPPEND"] | ["JARRAPPEND", _] | ["JARRAPPEND", _, _] => Command::Invalid { reason: r#""JARRAPPEND" requires an ID, a Path and Values"#.to_owned() },
+        ["JNUMINCRBY"] | ["JNUMINCRBY", _] | ["JNUMINCRBY", _, _] => Command::Invalid { reason: r#""JNUMINCRBY" requires an ID, a Path and a Number"#.to_owned() },
+        ["INDEX"] => Command::Invalid { reason: r#""INDEX" requires a Subcommand ('CREATE', 'DROP', 'LIST')"#.to_owned() },
+        ["INDEX", "DROP"] => Command::Invalid { reason: r#""INDEX DROP" requires a Name"#.to_owned() },
+        ["FIND"] | ["FIND", _] => Command::Invalid { reason: r#""FIND" requires an Index and a Value or Range"#.to_owned() },
+        ["SEARCH"] | ["SEARCH", _] => Command::Invalid { reason: r#""SEARCH" requires an Index and a Query"#.to_owned() },
+        ["VADD"] | ["VADD", _] => Command::Invalid { reason: r#""VADD" requires an ID and a Vector"#.to_owned() },
+        ["VGET"] => Command::Invalid { reason: r#""VGET" requires an ID"#.to_owned() },
+        ["VSEARCH"] | ["VSEARCH", _] | ["VSEARCH", _, _] => Command::Invalid { reason: r#""VSEARCH" requires an Index, a Count and a Vector"#.to_owned() },
+        ["EVAL"] => Command::Invalid { reason: r#""EVAL" requires a Script in braces"#.to_owned() },
+        ["EVALSHA"] | ["EVALSHA", _] => Command::Invalid { reason: r#""EVALSHA" requires a SHA and a Number of keys"#.to_owned() },
+        ["SCRIPT"] => Command::Invalid { reason: r#""SCRIPT" requires a Subcommand ('LOAD')"#.to_owned() },
+        ["SCRIPT", "LOAD"] => Command::Invalid { reason: r#""SCRIPT LOAD" requires a Script in braces"#.to_owned() },
+        ["SELECT"] => Command::Invalid { reason: r#""SELECT" requires a Database index"#.to_owned() },
+        ["SUB"] => Command::Invalid { reason: r#""SUB" requires a Pattern"#.to_owned() },
+        ["UNSUB"] => Command::Invalid { reason: r#""UNSUB" requires a Pattern"#.to_owned() },
         ["SET", _id] => Command::Invalid { reason: r#""SET" requires a Data"#.to_string() },
 
         ["PING"] => Command::Ping {  },
@@ -33,12 +334,22 @@ pub fn parse_command(input: String) -> Command {
         ["GET", id] => Command::Get { id: id.to_string() },
 
         ["LST", expr] => match parse_expr(expr) {
-            Ok(expr) => Command::List { expr },
+            Ok(expr) => Command::List { expr, namespace: None },
+            Err(err) => Command::Invalid { reason: err.to_owned() },
+        },
+
+        ["LST", expr, "IN", namespace] => match parse_expr(expr) {
+            Ok(expr) => Command::List { expr, namespace: Some(namespace.to_string()) },
             Err(err) => Command::Invalid { reason: err.to_owned() },
         },
 
         ["CNT", expr] => match parse_expr(expr) {
-            Ok(expr) => Command::Count { expr },
+            Ok(expr) => Command::Count { expr, namespace: None },
+            Err(err) => Command::Invalid { reason: err.to_string() },
+        },
+
+        ["CNT", expr, "IN", namespace] => match parse_expr(expr) {
+            Ok(expr) => Command::Count { expr, namespace: Some(namespace.to_string()) },
             Err(err) => Command::Invalid { reason: err.to_string() },
         },
 
@@ -49,11 +360,465 @@ pub fn parse_command(input: String) -> Command {
             Err(err) => Command::Invalid { reason: err.to_string() },
         },
 
+        ["AUTH", password] => Command::Auth { username: None, password: password.to_string() },
+
+        ["AUTH", username, password] => Command::Auth {
+            username: Some(username.to_string()),
+            password: password.to_string()
+        },
+
+        ["ACL", "WHOAMI"] => Command::AclWhoami {  },
+
+        ["ACL", "LIST"] => Command::AclList {  },
+
+        ["CONFIG", "RELOAD"] => Command::ConfigReload {  },
+
+        ["CONFIG", "GET", pattern] => Command::ConfigGet { pattern: pattern.to_string() },
+
+        // An empty value is allowed, `CONFIG SET requirepass` turns the password off.
+        ["CONFIG", "SET", name, value @ ..] => Command::ConfigSet { name: name.to_string(), value: value.join(" ") },
+
+        ["SHUTDOWN"] | ["SHUTDOWN", "SAVE"] => Command::Shutdown { save: true },
+
+        ["SHUTDOWN", "NOSAVE"] => Command::Shutdown { save: false },
+
+        ["SELECT", database] => match database.parse::<usize>() {
+            Ok(database) => Command::Select { database },
+            Err(_) => Command::Invalid { reason: "Invalid database index".to_string() },
+        },
+
+        ["NS", "LIST"] => Command::NamespaceList {  },
+
+        ["NS", "DROP", namespace] => Command::NamespaceDrop { namespace: namespace.to_string() },
+
+        ["EXISTS", ids @ ..] => Command::Exists { ids: ids.iter().map(|id| id.to_string()).collect() },
+
+        ["RENAME", from, to] => Command::Rename { from: from.to_string(), to: to.to_string(), replace: true },
+
+        ["RENAMENX", from, to] => Command::Rename { from: from.to_string(), to: to.to_string(), replace: false },
+
+        ["COPY", from, to] => Command::Copy { from: from.to_string(), to: to.to_string(), replace: false },
+
+        ["COPY", from, to, "REPLACE"] => Command::Copy { from: from.to_string(), to: to.to_string(), replace: true },
+
+        ["TYPE", id] => Command::Type { id: id.to_string() },
+
+        ["KEYS", expr] => match parse_expr(expr) {
+            Ok(expr) => Command::Keys { expr },
+            Err(err) => Command::Invalid { reason: err.to_string() },
+        },
+
+        ["APPEND", id, data @ ..] => Command::Append { id: id.to_string(), data: data.join(" ") },
+
+        ["STRLEN", id] => Command::StrLen { id: id.to_string() },
+
+        ["GETRANGE", id, range] => match parse_expr(range) {
+            Ok(Expr::Range(start, end)) => Command::GetRange { id: id.to_string(), start, end },
+            _ => Command::Invalid { reason: r#""GETRANGE" requires a Range (0..5)"#.to_string() },
+        },
+
+        ["SETRANGE", id, offset, data @ ..] => match offset.parse::<usize>() {
+            Ok(offset) => Command::SetRange { id: id.to_string(), offset, data: data.join(" ") },
+            Err(_) => Command::Invalid { reason: "Invalid offset".to_string() },
+        },
+
+        ["SETBIT", id, offset, bit] => match (offset.parse::<usize>(), *bit) {
+            (Ok(offset), "0") => Command::SetBit { id: id.to_string(), offset, bit: 0 },
+            (Ok(offset), "1") => Command::SetBit { id: id.to_string(), offset, bit: 1 },
+            (Err(_), _) => Command::Invalid { reason: "Invalid offset".to_string() },
+            _ => Command::Invalid { reason: "Invalid bit, valid values: (0, 1)".to_string() },
+        },
+
+        ["GETBIT", id, offset] => match offset.parse::<usize>() {
+            Ok(offset) => Command::GetBit { id: id.to_string(), offset },
+            Err(_) => Command::Invalid { reason: "Invalid offset".to_string() },
+        },
+
+        ["BITCOUNT", id] => Command::BitCount { id: id.to_string(), range: None },
+
+        ["BITCOUNT", id, range, unit @ ..] => {
+            let unit = match unit {
+                [] | ["BYTE"] => Unit::Byte,
+                ["BIT"] => Unit::Bit,
+                _ => return Command::Invalid { reason: "Invalid BITCOUNT unit, valid values: ('BYTE', 'BIT')".to_string() },
+            };
+
+            match parse_expr(range) {
+                Ok(Expr::Range(start, end)) => Command::BitCount { id: id.to_string(), range: Some((start, end, unit)) },
+                _ => Command::Invalid { reason: r#""BITCOUNT" requires a Range (0..5)"#.to_string() },
+            }
+        }
+
+        ["BITOP", op, destination, sources @ ..] => match op.parse::<Op>() {
+            Ok(Op::Not) if sources.len() != 1 => Command::Invalid { reason: r#""BITOP NOT" takes exactly one source ID"#.to_string() },
+            Ok(op) => Command::BitOp {
+                op,
+                destination: destination.to_string(),
+                sources: sources.iter().map(|id| id.to_string()).collect(),
+            },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["PFADD", id, elements @ ..] => Command::PfAdd {
+            id: id.to_string(),
+            elements: elements.iter().map(|element| element.to_string()).collect(),
+        },
+
+        ["PFCOUNT", ids @ ..] => Command::PfCount { ids: ids.iter().map(|id| id.to_string()).collect() },
+
+        ["PFMERGE", destination, sources @ ..] => Command::PfMerge {
+            destination: destination.to_string(),
+            sources: sources.iter().map(|id| id.to_string()).collect(),
+        },
+
+        ["XADD", id, "MAXLEN", maxlen, rest @ ..] => match maxlen.parse::<usize>() {
+            Ok(maxlen) => parse_xadd(id, rest, Some(maxlen)),
+            Err(_) => Command::Invalid { reason: "Invalid MAXLEN".to_string() },
+        },
+
+        ["XADD", id, rest @ ..] => parse_xadd(id, rest, None),
+
+        ["XRANGE", id, start, end, rest @ ..] => {
+            let count = match rest {
+                [] => None,
+                ["COUNT", count] => match count.parse::<usize>() {
+                    Ok(count) => Some(count),
+                    Err(_) => return Command::Invalid { reason: "Invalid COUNT".to_string() },
+                },
+                _ => return Command::Invalid { reason: "Invalid Command".to_string() },
+            };
+
+            match (StreamId::parse_bound(start, false), StreamId::parse_bound(end, true)) {
+                (Ok(start), Ok(end)) => Command::XRange { id: id.to_string(), start, end, count },
+                (Err(err), _) | (_, Err(err)) => Command::Invalid { reason: err },
+            }
+        }
+
+        ["XLEN", id] => Command::XLen { id: id.to_string() },
+
+        ["XTRIM", id, "MAXLEN", maxlen] => match maxlen.parse::<usize>() {
+            Ok(maxlen) => Command::XTrim { id: id.to_string(), maxlen },
+            Err(_) => Command::Invalid { reason: "Invalid MAXLEN".to_string() },
+        },
+
+        ["XGROUP", "CREATE", id, group, start, rest @ ..] => {
+            let mkstream = match rest {
+                [] => false,
+                ["MKSTREAM"] => true,
+                _ => return Command::Invalid { reason: "Invalid Command".to_string() },
+            };
+
+            match *start {
+                "$" => Command::XGroupCreate { id: id.to_string(), group: group.to_string(), start: None, mkstream },
+                _ => match start.parse::<StreamId>() {
+                    Ok(start) => Command::XGroupCreate { id: id.to_string(), group: group.to_string(), start: Some(start), mkstream },
+                    Err(err) => Command::Invalid { reason: err },
+                },
+            }
+        }
+
+        ["XREADGROUP", "GROUP", group, consumer, rest @ ..] => {
+            let (count, rest) = match rest {
+                ["COUNT", count, rest @ ..] => match count.parse::<usize>() {
+                    Ok(count) => (Some(count), rest),
+                    Err(_) => return Command::Invalid { reason: "Invalid COUNT".to_string() },
+                },
+                _ => (None, rest),
+            };
+
+            let (id, after) = match rest {
+                ["STREAMS", id, ">"] => (id, None),
+                ["STREAMS", id, after] => match after.parse::<StreamId>() {
+                    Ok(after) => (id, Some(after)),
+                    Err(err) => return Command::Invalid { reason: err },
+                },
+                _ => return Command::Invalid { reason: r#""XREADGROUP" requires STREAMS <id> <entry ID or >>"#.to_string() },
+            };
+
+            Command::XReadGroup { id: id.to_string(), group: group.to_string(), consumer: consumer.to_string(), after, count }
+        }
+
+        ["XACK", id, group, entries @ ..] => match entries.iter().map(|entry| entry.parse::<StreamId>()).collect() {
+            Ok(entries) => Command::XAck { id: id.to_string(), group: group.to_string(), entries },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["XPENDING", id, group] => Command::XPending { id: id.to_string(), group: group.to_string() },
+
+        ["GEOADD", id, members @ ..] => {
+            if members.len() % 3 != 0 {
+                return Command::Invalid { reason: r#""GEOADD" requires longitude latitude member triples"#.to_string() };
+            }
+
+            match members.chunks(3).map(|triple| Ok((Point::parse(triple[0], triple[1])?, triple[2].to_string()))).collect() {
+                Ok(members) => Command::GeoAdd { id: id.to_string(), members },
+                Err(err) => Command::Invalid { reason: err },
+            }
+        }
+
+        ["GEOPOS", id, members @ ..] => Command::GeoPos {
+            id: id.to_string(),
+            members: members.iter().map(|member| member.to_string()).collect(),
+        },
+
+        ["GEODIST", id, from, to, unit @ ..] => {
+            let unit = match unit {
+                [] => Ok(geo::Unit::default()),
+                [unit] => unit.parse(),
+                _ => Err("Invalid Command".to_string()),
+            };
+
+            match unit {
+                Ok(unit) => Command::GeoDist { id: id.to_string(), from: from.to_string(), to: to.to_string(), unit },
+                Err(err) => Command::Invalid { reason: err },
+            }
+        }
+
+        ["GEOSEARCH", id, rest @ ..] => match parse_geosearch(id, rest) {
+            Ok(command) => command,
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["JSET", id, path, value @ ..] => match (path.parse::<Path>(), json::parse(&value.join(" "))) {
+            (Ok(path), Ok(value)) => Command::JsonSet { id: id.to_string(), path, value },
+            (Err(err), _) | (_, Err(err)) => Command::Invalid { reason: err },
+        },
+
+        ["JGET", id] => Command::JsonGet { id: id.to_string(), path: Path::default() },
+
+        ["JGET", id, path] => match path.parse::<Path>() {
+            Ok(path) => Command::JsonGet { id: id.to_string(), path },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["JDEL", id] => Command::JsonDelete { id: id.to_string(), path: Path::default() },
+
+        ["JDEL", id, path] => match path.parse::<Path>() {
+            Ok(path) => Command::JsonDelete { id: id.to_string(), path },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["JARRAPPEND", id, path, values @ ..] => match (path.parse::<Path>(), json::parse_many(&values.join(" "))) {
+            (Ok(path), Ok(values)) => Command::JsonArrAppend { id: id.to_string(), path, values },
+            (Err(err), _) | (_, Err(err)) => Command::Invalid { reason: err },
+        },
+
+        ["JNUMINCRBY", id, path, by] => match (path.parse::<Path>(), json::parse(by)) {
+            (Ok(path), Ok(serde_json::Value::Number(by))) => Command::JsonNumIncrBy { id: id.to_string(), path, by },
+            (Err(err), _) => Command::Invalid { reason: err },
+            _ => Command::Invalid { reason: format!("Invalid number {by:?}") },
+        },
+
+        ["INDEX", "CREATE", name, "ON", pattern, "FIELD", path] => match path.parse::<Path>() {
+            Ok(path) => Command::IndexCreate { name: name.to_string(), pattern: pattern.to_string(), field: Field::Value(path) },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["INDEX", "CREATE", name, "ON", pattern, "TEXT"] => {
+            Command::IndexCreate { name: name.to_string(), pattern: pattern.to_string(), field: Field::Text(None) }
+        }
+
+        ["INDEX", "CREATE", name, "ON", pattern, "TEXT", "FIELD", path] => match path.parse::<Path>() {
+            Ok(path) => Command::IndexCreate { name: name.to_string(), pattern: pattern.to_string(), field: Field::Text(Some(path)) },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["INDEX", "CREATE", name, "ON", pattern, "VECTOR", dimensions, options @ ..] => match parse_vector_field(dimensions, options) {
+            Ok(field) => Command::IndexCreate { name: name.to_string(), pattern: pattern.to_string(), field },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["INDEX", "CREATE", ..] => Command::Invalid { reason: r#""INDEX CREATE" requires a Name, ON <pattern> and FIELD <path>, TEXT [FIELD <path>] or VECTOR <dimensions>"#.to_owned() },
+
+        ["INDEX", "DROP", name] => Command::IndexDrop { name: name.to_string() },
+
+        ["INDEX", "LIST"] => Command::IndexList {  },
+
+        ["FIND", name, query @ ..] => match query.join(" ").parse::<Query>() {
+            Ok(query) => Command::Find { name: name.to_string(), query },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["SEARCH", name, query @ ..] => match query.join(" ").parse::<search::Query>() {
+            Ok(query) => Command::Search { name: name.to_string(), query },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["VADD", id, components @ ..] => match Vector::parse(components) {
+            Ok(vector) => Command::VAdd { id: id.to_string(), vector },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["VGET", id] => Command::VGet { id: id.to_string() },
+
+        ["VSEARCH", name, k, rest @ ..] => match parse_vsearch(name, k, rest) {
+            Ok(command) => command,
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["EVALSHA", sha, numkeys, rest @ ..] => match parse_keys(numkeys, rest) {
+            Ok((keys, args)) => Command::EvalSha { sha: sha.to_string(), keys, args },
+            Err(err) => Command::Invalid { reason: err },
+        },
+
+        ["FLUSHDB"] => Command::FlushDb {  },
+
+        ["FLUSHALL"] => Command::FlushAll {  },
+
+        ["SUB", pattern] => Command::Subscribe { pattern: pattern.to_string() },
+
+        ["UNSUB", pattern] => Command::Unsubscribe { pattern: pattern.to_string() },
+
         _ => Command::Invalid { reason: "Invalid Command".to_string() },
     }
 }
 
-fn parse_expr(expr_str: &str) -> Result<Expr, &str> {
+/// The rest of `XADD` after the id and `MAXLEN`: the entry id or `*` and the field value pairs.
+fn parse_xadd(id: &str, rest: &[&str], maxlen: Option<usize>) -> Command {
+    let (entry, fields) = match rest {
+        [entry, fields @ ..] if !fields.is_empty() && fields.len() % 2 == 0 => (entry, fields),
+        _ => return Command::Invalid { reason: r#""XADD" requires an entry ID and field value pairs"#.to_string() },
+    };
+
+    let entry = match *entry {
+        "*" => None,
+        _ => match entry.parse::<StreamId>() {
+            Ok(entry) => Some(entry),
+            Err(err) => return Command::Invalid { reason: err },
+        },
+    };
+
+    let fields = fields.chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string())).collect();
+
+    Command::XAdd { id: id.to_string(), entry, fields, maxlen }
+}
+
+/// The options of `GEOSEARCH`: `FROMMEMBER` or `FROMLONLAT`, `BYRADIUS` or `BYBOX`, then the optional ones.
+fn parse_geosearch(id: &str, mut rest: &[&str]) -> Result<Command, String> {
+    let (mut center, mut shape, mut options) = (None, None, SearchOptions::default());
+    let mut unit = geo::Unit::default();
+
+    loop {
+        rest = match rest {
+            [] => break,
+            ["FROMMEMBER", member, rest @ ..] => {
+                center = Some(Center::Member(member.to_string()));
+                rest
+            }
+            ["FROMLONLAT", lon, lat, rest @ ..] => {
+                center = Some(Center::Point(Point::parse(lon, lat)?));
+                rest
+            }
+            ["BYRADIUS", radius, by, rest @ ..] => {
+                unit = by.parse()?;
+                shape = Some(Shape::parse(&[radius], unit)?);
+                rest
+            }
+            ["BYBOX", width, height, by, rest @ ..] => {
+                unit = by.parse()?;
+                shape = Some(Shape::parse(&[width, height], unit)?);
+                rest
+            }
+            ["ASC", rest @ ..] => {
+                options.descending = false;
+                rest
+            }
+            ["DESC", rest @ ..] => {
+                options.descending = true;
+                rest
+            }
+            ["COUNT", count, rest @ ..] => {
+                options.count = Some(count.parse::<usize>().map_err(|_| "Invalid COUNT".to_string())?);
+                rest
+            }
+            ["WITHDIST", rest @ ..] => {
+                options.with_dist = true;
+                rest
+            }
+            ["WITHCOORD", rest @ ..] => {
+                options.with_coord = true;
+                rest
+            }
+            [option, ..] => return Err(format!("Invalid GEOSEARCH option {option:?}")),
+        };
+    }
+
+    match (center, shape) {
+        (Some(center), Some(shape)) => Ok(Command::GeoSearch { id: id.to_string(), center, shape, unit, options }),
+        (None, _) => Err(r#""GEOSEARCH" requires FROMMEMBER <member> or FROMLONLAT <lon> <lat>"#.to_string()),
+        (_, None) => Err(r#""GEOSEARCH" requires BYRADIUS <radius> <unit> or BYBOX <width> <height> <unit>"#.to_string()),
+    }
+}
+
+/// The options of a vector index: the metric, `COSINE` by default or `L2`, and `HNSW` for approximate searches.
+fn parse_vector_field(dimensions: &str, options: &[&str]) -> Result<Field, String> {
+    let dimensions = match dimensions.parse::<usize>() {
+        Ok(dimensions) if dimensions > 0 => dimensions,
+        _ => return Err(format!("Invalid dimensions {dimensions:?}")),
+    };
+
+    let (mut metric, mut approximate) = (Metric::default(), false);
+
+    for option in options {
+        match *option {
+            "HNSW" => approximate = true,
+            option => metric = option.parse()?,
+        }
+    }
+
+    Ok(Field::Vector { dimensions, metric, approximate })
+}
+
+/// `VSEARCH <index> <k> [EXACT] [WITHDIST] <vector>`.
+fn parse_vsearch(name: &str, k: &str, mut rest: &[&str]) -> Result<Command, String> {
+    let k = k.parse::<usize>().map_err(|_| format!("Invalid count {k:?}"))?;
+    let (mut exact, mut with_dist) = (false, false);
+
+    loop {
+        rest = match rest {
+            ["EXACT", rest @ ..] => {
+                exact = true;
+                rest
+            }
+            ["WITHDIST", rest @ ..] => {
+                with_dist = true;
+                rest
+            }
+            _ => break,
+        };
+    }
+
+    match rest {
+        [] => Err(r#""VSEARCH" requires an Index, a Count and a Vector"#.to_string()),
+        components => Ok(Command::VSearch { name: name.to_string(), k, vector: Vector::parse(components)?, exact, with_dist }),
+    }
+}
+
+/// The rest of `EVAL`: the script in braces, the number of keys, then the keys and the args.
+fn parse_eval(input: &str) -> Result<Command, String> {
+    let (script, rest) = script::split("EVAL", input)?;
+    let rest: Vec<&str> = rest.split_whitespace().collect();
+    let (keys, args) = match rest.as_slice() {
+        [numkeys, rest @ ..] => parse_keys(numkeys, rest)?,
+        [] => return Err(r#""EVAL" requires a Number of keys"#.to_string()),
+    };
+
+    Ok(Command::Eval { script: script.to_string(), keys, args })
+}
+
+/// Splits what follows `numkeys` into the keys and the args after them.
+fn parse_keys(numkeys: &str, rest: &[&str]) -> Result<(Vec<String>, Vec<String>), String> {
+    let numkeys = numkeys.parse::<usize>().map_err(|_| format!("Invalid number of keys {numkeys:?}"))?;
+
+    if numkeys > rest.len() {
+        return Err("Number of keys can't be greater than number of args".to_string());
+    }
+
+    let (keys, args) = rest.split_at(numkeys);
+    Ok((keys.iter().map(|key| key.to_string()).collect(), args.iter().map(|arg| arg.to_string()).collect()))
+}
+
+pub fn parse_expr(expr_str: &str) -> Result<Expr, &str> {
     if let Ok(number) = expr_str.parse::<i32>() {
         return Ok(Expr::Number(number));
     }
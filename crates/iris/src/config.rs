@@ -0,0 +1,394 @@
+use clap::{parser::ValueSource, ArgMatches};
+use serde::Deserialize;
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+use crate::{
+    memory::{self, Policy},
+    server::{Listeners, UnixSocket},
+};
+
+/// Every setting by the name used in the config file, on the command line and by `CONFIG`.
+pub const NAMES: [&str; 19] = [
+    "bind",
+    "port",
+    "format",
+    "debug",
+    "http-port",
+    "ws-port",
+    "unix-socket",
+    "unix-socket-perm",
+    "requirepass",
+    "passfile",
+    "aclfile",
+    "tls-cert",
+    "tls-key",
+    "tls-ca-cert",
+    "maxmemory",
+    "maxmemory-policy",
+    "databases",
+    "namespace-delimiter",
+    "script-time-limit",
+];
+
+/// The settings `CONFIG SET` and `CONFIG RELOAD` can change on a running server.
+pub const RELOADABLE: [&str; 7] = [
+    "format",
+    "debug",
+    "requirepass",
+    "passfile",
+    "maxmemory",
+    "maxmemory-policy",
+    "script-time-limit",
+];
+
+/// Settings as written in a config file or given on the command line, where anything can be missing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    bind: Option<Vec<IpAddr>>,
+    port: Option<u16>,
+    format: Option<String>,
+    debug: Option<bool>,
+    http_port: Option<u16>,
+    ws_port: Option<u16>,
+    unix_socket: Option<String>,
+    unix_socket_perm: Option<String>,
+    requirepass: Option<String>,
+    passfile: Option<String>,
+    aclfile: Option<String>,
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    tls_ca_cert: Option<String>,
+    maxmemory: Option<String>,
+    maxmemory_policy: Option<String>,
+    databases: Option<usize>,
+    namespace_delimiter: Option<String>,
+    script_time_limit: Option<u64>,
+}
+
+impl Config {
+    pub fn from_file(path: &str) -> Result<Config, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read the config file {path}: {err}"))?;
+
+        toml::from_str(&contents).map_err(|err| format!("Invalid config file {path}: {}", err.message()))
+    }
+
+    /// Only picks up flags that were actually passed, so they override the config file but defaults don't.
+    pub fn from_args(matches: &ArgMatches) -> Config {
+        Config {
+            bind: given(matches, "bind").then(|| matches.get_many::<IpAddr>("bind").unwrap().copied().collect()),
+            port: arg(matches, "port"),
+            format: arg(matches, "format"),
+            debug: arg(matches, "debug"),
+            http_port: arg(matches, "http-port"),
+            ws_port: arg(matches, "ws-port"),
+            unix_socket: arg(matches, "unix-socket"),
+            unix_socket_perm: arg(matches, "unix-socket-perm"),
+            requirepass: arg(matches, "requirepass"),
+            passfile: arg(matches, "passfile"),
+            aclfile: arg(matches, "aclfile"),
+            tls_cert: arg(matches, "tls-cert"),
+            tls_key: arg(matches, "tls-key"),
+            tls_ca_cert: arg(matches, "tls-ca-cert"),
+            maxmemory: arg(matches, "maxmemory"),
+            maxmemory_policy: arg(matches, "maxmemory-policy"),
+            databases: arg(matches, "databases"),
+            namespace_delimiter: arg(matches, "namespace-delimiter"),
+            script_time_limit: arg(matches, "script-time-limit"),
+        }
+    }
+
+    /// Fills in everything `overrides` doesn't set from `self`.
+    pub fn merge(self, overrides: Config) -> Config {
+        // The password comes from one place, so overriding either replaces both.
+        let (requirepass, passfile) = match (overrides.requirepass, overrides.passfile) {
+            (None, None) => (self.requirepass, self.passfile),
+            given => given,
+        };
+
+        Config {
+            bind: overrides.bind.or(self.bind),
+            port: overrides.port.or(self.port),
+            format: overrides.format.or(self.format),
+            debug: overrides.debug.or(self.debug),
+            http_port: overrides.http_port.or(self.http_port),
+            ws_port: overrides.ws_port.or(self.ws_port),
+            unix_socket: overrides.unix_socket.or(self.unix_socket),
+            unix_socket_perm: overrides.unix_socket_perm.or(self.unix_socket_perm),
+            requirepass,
+            passfile,
+            aclfile: overrides.aclfile.or(self.aclfile),
+            tls_cert: overrides.tls_cert.or(self.tls_cert),
+            tls_key: overrides.tls_key.or(self.tls_key),
+            tls_ca_cert: overrides.tls_ca_cert.or(self.tls_ca_cert),
+            maxmemory: overrides.maxmemory.or(self.maxmemory),
+            maxmemory_policy: overrides.maxmemory_policy.or(self.maxmemory_policy),
+            databases: overrides.databases.or(self.databases),
+            namespace_delimiter: overrides.namespace_delimiter.or(self.namespace_delimiter),
+            script_time_limit: overrides.script_time_limit.or(self.script_time_limit),
+        }
+    }
+}
+
+fn given(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+fn arg<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, id: &str) -> Option<T> {
+    match given(matches, id) {
+        true => matches.get_one::<T>(id).cloned(),
+        false => None,
+    }
+}
+
+/// The validated settings the server runs with.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The config file these settings were loaded from, if any.
+    pub path: Option<String>,
+    /// The flags given on the command line, which keep overriding the file on reload.
+    pub args: Config,
+    pub bind: Vec<IpAddr>,
+    pub port: u16,
+    pub format: String,
+    pub debug: bool,
+    pub http_port: Option<u16>,
+    pub ws_port: Option<u16>,
+    pub unix_socket: Option<String>,
+    pub unix_socket_perm: Option<u32>,
+    pub requirepass: Option<String>,
+    pub passfile: Option<String>,
+    pub aclfile: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_ca_cert: Option<String>,
+    /// The most bytes the keys may take up, `0` for no limit.
+    pub maxmemory: usize,
+    pub maxmemory_policy: Policy,
+    /// How many databases `SELECT` can pick from.
+    pub databases: usize,
+    /// Separates the namespaces of an id, like the `:`s in `service:entity:id`.
+    pub namespace_delimiter: String,
+    /// How many milliseconds a script may run before it's stopped, `0` for no limit.
+    /// Every command touching keys waits for a running script, so this also bounds how long they wait.
+    pub script_time_limit: u64,
+}
+
+/// Loads `--config` if given and applies the command line on top of it.
+pub fn load(matches: &ArgMatches) -> Result<Settings, String> {
+    let path = matches.get_one::<String>("config").cloned();
+
+    let file = match &path {
+        Some(path) => Config::from_file(path)?,
+        None => Config::default(),
+    };
+
+    Settings::resolve(file, Config::from_args(matches), path)
+}
+
+impl Settings {
+    pub fn resolve(file: Config, args: Config, path: Option<String>) -> Result<Settings, String> {
+        let config = file.merge(args.clone());
+
+        let format = config.format.unwrap_or("default".to_string());
+        validate_format(&format)?;
+
+        let bind = config.bind.unwrap_or(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+        if bind.is_empty() {
+            return Err("bind needs at least one address".to_string());
+        }
+
+        if config.tls_cert.is_some() != config.tls_key.is_some() {
+            return Err("tls-cert and tls-key have to be set together".to_string());
+        }
+
+        if config.tls_ca_cert.is_some() && config.tls_cert.is_none() {
+            return Err("tls-ca-cert needs tls-cert and tls-key".to_string());
+        }
+
+        if config.unix_socket_perm.is_some() && config.unix_socket.is_none() {
+            return Err("unix-socket-perm needs unix-socket".to_string());
+        }
+
+        let unix_socket_perm = match &config.unix_socket_perm {
+            Some(perm) => match u32::from_str_radix(perm, 8) {
+                Ok(mode) if mode <= 0o777 => Some(mode),
+                _ => return Err(format!("Invalid unix-socket-perm {perm:?}, expected octal bits like 770")),
+            },
+            None => None,
+        };
+
+        let requirepass = match (&config.requirepass, &config.passfile) {
+            (Some(_), Some(_)) => return Err("requirepass and passfile can't both be set".to_string()),
+            (_, Some(passfile)) => match fs::read_to_string(passfile) {
+                Ok(contents) => contents.lines().next().map(str::to_string),
+                Err(err) => return Err(format!("Failed to read the password file: {err}")),
+            },
+            (requirepass, None) => requirepass.clone(),
+        };
+
+        let maxmemory = match &config.maxmemory {
+            Some(maxmemory) => memory::parse_bytes(maxmemory)?,
+            None => 0,
+        };
+
+        let maxmemory_policy = match &config.maxmemory_policy {
+            Some(policy) => policy.parse()?,
+            None => Policy::default(),
+        };
+
+        let databases = config.databases.unwrap_or(16);
+        if databases == 0 {
+            return Err("databases needs to be at least 1".to_string());
+        }
+
+        let namespace_delimiter = config.namespace_delimiter.unwrap_or(":".to_string());
+        if namespace_delimiter.is_empty() || namespace_delimiter.contains(char::is_whitespace) {
+            return Err("namespace-delimiter can't be empty or contain whitespace".to_string());
+        }
+
+        Ok(Settings {
+            path,
+            args,
+            bind,
+            port: config.port.unwrap_or(3000),
+            format,
+            debug: config.debug.unwrap_or(false),
+            http_port: config.http_port,
+            ws_port: config.ws_port,
+            unix_socket: config.unix_socket,
+            unix_socket_perm,
+            requirepass,
+            passfile: config.passfile,
+            aclfile: config.aclfile,
+            tls_cert: config.tls_cert,
+            tls_key: config.tls_key,
+            tls_ca_cert: config.tls_ca_cert,
+            maxmemory,
+            maxmemory_policy,
+            databases,
+            namespace_delimiter,
+            script_time_limit: config.script_time_limit.unwrap_or(5000),
+        })
+    }
+
+    /// Re-reads the config file and applies the reloadable settings, all or nothing.
+    /// Returns the changed settings that only take effect after a restart.
+    pub fn reload(&mut self) -> Result<Vec<&'static str>, String> {
+        let path = self
+            .path
+            .clone()
+            .ok_or("No config file to reload, start the server with --config")?;
+
+        let fresh = Settings::resolve(Config::from_file(&path)?, self.args.clone(), Some(path))?;
+
+        let restart = NAMES
+            .into_iter()
+            .filter(|name| !RELOADABLE.contains(name) && self.get(name) != fresh.get(name))
+            .collect();
+
+        self.format = fresh.format;
+        self.debug = fresh.debug;
+        self.requirepass = fresh.requirepass;
+        self.passfile = fresh.passfile;
+        self.maxmemory = fresh.maxmemory;
+        self.maxmemory_policy = fresh.maxmemory_policy;
+        self.script_time_limit = fresh.script_time_limit;
+
+        Ok(restart)
+    }
+
+    pub fn listeners(&self) -> Listeners {
+        let addrs = |port: u16| self.bind.iter().map(|ip| SocketAddr::new(*ip, port)).collect::<Vec<_>>();
+
+        let unix = self.unix_socket.as_ref().map(|path| UnixSocket {
+            path: path.to_string(),
+            mode: self.unix_socket_perm,
+        });
+
+        // Like redis, port 0 turns tcp off when there's a unix socket to listen on instead.
+        let tcp = match (self.port, &unix) {
+            (0, Some(_)) => vec![],
+            (port, _) => addrs(port),
+        };
+
+        Listeners {
+            tcp,
+            unix,
+            http: self.http_port.map(addrs).unwrap_or_default(),
+            ws: self.ws_port.map(addrs).unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        let port = |value: Option<u16>| value.map(|port| port.to_string()).unwrap_or_default();
+
+        let value = match name {
+            "bind" => self.bind.iter().map(IpAddr::to_string).collect::<Vec<_>>().join(" "),
+            "port" => self.port.to_string(),
+            "format" => self.format.clone(),
+            "debug" => self.debug.to_string(),
+            "http-port" => port(self.http_port),
+            "ws-port" => port(self.ws_port),
+            "unix-socket" => optional(&self.unix_socket),
+            "unix-socket-perm" => self.unix_socket_perm.map(|mode| format!("{mode:o}")).unwrap_or_default(),
+            // Never hand the password out, only whether there is one.
+            "requirepass" => self.requirepass.as_ref().map(|_| "***".to_string()).unwrap_or_default(),
+            "passfile" => optional(&self.passfile),
+            "aclfile" => optional(&self.aclfile),
+            "tls-cert" => optional(&self.tls_cert),
+            "tls-key" => optional(&self.tls_key),
+            "tls-ca-cert" => optional(&self.tls_ca_cert),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.to_string(),
+            "databases" => self.databases.to_string(),
+            "namespace-delimiter" => self.namespace_delimiter.clone(),
+            "script-time-limit" => self.script_time_limit.to_string(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// Changes a setting that is safe to change on a running server (see [`RELOADABLE`]).
+    /// An empty `requirepass` turns the password off.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "format" => {
+                validate_format(value)?;
+                self.format = value.to_string();
+            }
+            "debug" => {
+                self.debug = value
+                    .parse::<bool>()
+                    .map_err(|_| format!("Invalid debug {value:?}, expected true or false"))?;
+            }
+            "requirepass" => {
+                self.requirepass = Some(value.to_string()).filter(|password| !password.is_empty());
+            }
+            "maxmemory" => self.maxmemory = memory::parse_bytes(value)?,
+            "maxmemory-policy" => self.maxmemory_policy = value.parse()?,
+            "script-time-limit" => {
+                self.script_time_limit = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid script-time-limit {value:?}, expected milliseconds"))?;
+            }
+            "passfile" => return Err("passfile can only be changed in the config file, then CONFIG RELOAD".to_string()),
+            _ if NAMES.contains(&name) => return Err(format!("{name} can't be changed without a restart")),
+            _ => return Err(format!("Unknown config {name:?}")),
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_format(format: &str) -> Result<(), String> {
+    match format {
+        "default" | "json" => Ok(()),
+        _ => Err(format!("Invalid format {format:?}, valid values: ('default', 'json')")),
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+/// A change to a key, tagged with the database it happened in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Set { database: usize, id: String },
+    Delete { database: usize, id: String },
+}
+
+impl Event {
+    pub fn id(&self) -> &str {
+        match self {
+            Event::Set { id, .. } | Event::Delete { id, .. } => id,
+        }
+    }
+
+    pub fn database(&self) -> usize {
+        match self {
+            Event::Set { database, .. } | Event::Delete { database, .. } => *database,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Set { id, .. } => write!(f, "set {id}"),
+            Event::Delete { id, .. } => write!(f, "del {id}"),
+        }
+    }
+}
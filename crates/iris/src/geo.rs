@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+/// Bits per coordinate, interleaved into a 52 bit geohash like redis does.
+const STEP: u32 = 26;
+
+/// How far north and south positions go, the limits of web mercator.
+const LAT_MIN: f64 = -85.05112878;
+const LAT_MAX: f64 = 85.05112878;
+const LON_MIN: f64 = -180.0;
+const LON_MAX: f64 = 180.0;
+
+/// The earth radius redis uses, so distances match.
+const EARTH_RADIUS: f64 = 6372797.560856;
+
+/// What a member costs besides its name, its slot in both the lookup map and the index.
+pub const MEMBER_OVERHEAD: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unit {
+    #[default]
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+impl Unit {
+    pub fn meters(&self) -> f64 {
+        match self {
+            Unit::Meters => 1.0,
+            Unit::Kilometers => 1000.0,
+            Unit::Feet => 0.3048,
+            Unit::Miles => 1609.34,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(unit: &str) -> Result<Unit, String> {
+        match unit.to_ascii_uppercase().as_str() {
+            "M" => Ok(Unit::Meters),
+            "KM" => Ok(Unit::Kilometers),
+            "FT" => Ok(Unit::Feet),
+            "MI" => Ok(Unit::Miles),
+            _ => Err(format!("Invalid unit {unit:?}, valid values: ('M', 'KM', 'FT', 'MI')")),
+        }
+    }
+}
+
+/// A longitude and latitude in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+/// [`Point::new`] refuses `NaN`, so every point equals itself.
+impl Eq for Point {}
+
+impl Point {
+    pub fn new(lon: f64, lat: f64) -> Result<Point, String> {
+        if !(LON_MIN..=LON_MAX).contains(&lon) || !(LAT_MIN..=LAT_MAX).contains(&lat) {
+            return Err(format!("Invalid longitude,latitude pair {lon:.6},{lat:.6}"));
+        }
+
+        Ok(Point { lon, lat })
+    }
+
+    /// Parses `<lon> <lat>` arguments.
+    pub fn parse(lon: &str, lat: &str) -> Result<Point, String> {
+        match (lon.parse::<f64>(), lat.parse::<f64>()) {
+            (Ok(lon), Ok(lat)) => Point::new(lon, lat),
+            _ => Err(format!("Invalid longitude,latitude pair {lon},{lat}")),
+        }
+    }
+
+    /// The great circle distance in meters.
+    pub fn distance(&self, other: &Point) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let u = ((lat2 - lat1) / 2.0).sin();
+        let v = ((other.lon - self.lon).to_radians() / 2.0).sin();
+
+        2.0 * EARTH_RADIUS * (u * u + lat1.cos() * lat2.cos() * v * v).sqrt().asin()
+    }
+}
+
+/// The area `GEOSEARCH` looks in, in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+/// [`Shape::parse`] refuses `NaN`, so every shape equals itself.
+impl Eq for Shape {}
+
+impl Shape {
+    /// Parses the sizes of `BYRADIUS` or `BYBOX` in `unit`.
+    pub fn parse(sizes: &[&str], unit: Unit) -> Result<Shape, String> {
+        let sizes = sizes
+            .iter()
+            .map(|size| match size.parse::<f64>() {
+                Ok(size) if size >= 0.0 && size.is_finite() => Ok(size * unit.meters()),
+                _ => Err(format!("Invalid size {size:?}")),
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+
+        match sizes.as_slice() {
+            [radius] => Ok(Shape::Radius(*radius)),
+            [width, height] => Ok(Shape::Box { width: *width, height: *height }),
+            _ => Err("Invalid shape".to_string()),
+        }
+    }
+
+    /// How far the shape reaches from its center, north-south and east-west.
+    fn extent(&self) -> (f64, f64) {
+        match self {
+            Shape::Radius(radius) => (*radius, *radius),
+            Shape::Box { width, height } => (height / 2.0, width / 2.0),
+        }
+    }
+
+    /// The distance from `center` when `point` is in the shape.
+    fn contains(&self, center: &Point, point: &Point) -> Option<f64> {
+        let distance = center.distance(point);
+
+        match self {
+            Shape::Radius(radius) => (distance <= *radius).then_some(distance),
+            Shape::Box { width, height } => {
+                let north_south = point.distance(&Point { lon: point.lon, lat: center.lat });
+                let east_west = point.distance(&Point { lon: center.lon, lat: point.lat });
+
+                (north_south <= height / 2.0 && east_west <= width / 2.0).then_some(distance)
+            }
+        }
+    }
+}
+
+/// Named positions, indexed by geohash so searches only look at the cells around the area.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Geo {
+    members: HashMap<String, u64>,
+    /// Every member sorted by geohash. Nearby positions share a prefix, so a cell is one range of it.
+    index: BTreeSet<(u64, String)>,
+    /// Each member name plus [`MEMBER_OVERHEAD`] for its hash and its entry in `index`.
+    size: usize,
+}
+
+/// What [`Geo::add`] did with a member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Added {
+    New,
+    Moved,
+    /// It was already there at the same position.
+    Unchanged,
+}
+
+impl Geo {
+    /// Adds or moves a member.
+    pub fn add(&mut self, member: &str, point: Point) -> Added {
+        let hash = encode(point, STEP);
+
+        match self.members.insert(member.to_string(), hash) {
+            Some(old) if old == hash => Added::Unchanged,
+            Some(old) => {
+                self.index.remove(&(old, member.to_string()));
+                self.index.insert((hash, member.to_string()));
+                Added::Moved
+            }
+            None => {
+                self.index.insert((hash, member.to_string()));
+                self.size += member.len() + MEMBER_OVERHEAD;
+                Added::New
+            }
+        }
+    }
+
+    /// The position of a member, the center of its geohash cell so a little off from what was added.
+    pub fn position(&self, member: &str) -> Option<Point> {
+        self.members.get(member).map(|hash| decode(*hash, STEP))
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// The members in `shape` around `center` with their distance in meters, closest first.
+    pub fn search(&self, center: Point, shape: Shape) -> Vec<(&String, f64)> {
+        let mut found: Vec<(&String, f64)> = cells(center, shape)
+            .into_iter()
+            .flat_map(|(start, end)| self.index.range((start, String::new())..(end, String::new())))
+            .filter_map(|(hash, member)| Some((member, shape.contains(&center, &decode(*hash, STEP))?)))
+            .collect();
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        found
+    }
+
+    /// The approximate bytes the members take up.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Interleaves `step` bits of longitude and latitude, longitude first.
+fn encode(point: Point, step: u32) -> u64 {
+    let cells = (1u64 << step) as f64;
+    let scale = |value: f64, min: f64, max: f64| (((value - min) / (max - min)) * cells).min(cells - 1.0) as u64;
+
+    let lat = scale(point.lat, LAT_MIN, LAT_MAX);
+    let lon = scale(point.lon, LON_MIN, LON_MAX);
+
+    (0..step).rev().fold(0, |hash, bit| (hash << 2) | ((lon >> bit) & 1) << 1 | ((lat >> bit) & 1))
+}
+
+/// The center of a cell.
+fn decode(hash: u64, step: u32) -> Point {
+    let (mut lat, mut lon) = (0u64, 0u64);
+
+    for bit in (0..step).rev() {
+        lon = (lon << 1) | ((hash >> (bit * 2 + 1)) & 1);
+        lat = (lat << 1) | ((hash >> (bit * 2)) & 1);
+    }
+
+    let cells = (1u64 << step) as f64;
+    let unscale = |cell: u64, min: f64, max: f64| min + (cell as f64 + 0.5) / cells * (max - min);
+
+    Point { lon: unscale(lon, LON_MIN, LON_MAX), lat: unscale(lat, LAT_MIN, LAT_MAX) }
+}
+
+/// The ranges of 52 bit geohashes in the cells around `center` that together cover `shape`.
+///
+/// Picks the smallest cells that are still bigger than the shape reaches, so it's covered by the cell of the
+/// center and its eight neighbours.
+fn cells(center: Point, shape: Shape) -> Vec<(u64, u64)> {
+    let (north_south, east_west) = shape.extent();
+    let meters_per_degree = EARTH_RADIUS.to_radians();
+
+    // Cells narrow towards the poles, so size them at whichever edge of the shape is furthest from the equator.
+    let lat = (center.lat.abs() + north_south / meters_per_degree).min(90.0);
+
+    let step = (1..=STEP)
+        .rev()
+        .find(|step| {
+            let cells = (1u64 << step) as f64;
+            let height = (LAT_MAX - LAT_MIN) / cells * meters_per_degree;
+            let width = (LON_MAX - LON_MIN) / cells * meters_per_degree * lat.to_radians().cos();
+
+            height >= north_south && width >= east_west
+        })
+        .unwrap_or(0);
+
+    if step == 0 {
+        return vec![(0, 1 << (STEP * 2))];
+    }
+
+    let cells = (1u64 << step) as f64;
+    let (height, width) = ((LAT_MAX - LAT_MIN) / cells, (LON_MAX - LON_MIN) / cells);
+    let shift = (STEP - step) * 2;
+
+    let mut ranges: Vec<(u64, u64)> = [-1.0, 0.0, 1.0]
+        .iter()
+        .flat_map(|dy| [-1.0, 0.0, 1.0].iter().map(move |dx| (dy, dx)))
+        .filter_map(|(dy, dx)| {
+            let lat = center.lat + dy * height;
+            if !(LAT_MIN..=LAT_MAX).contains(&lat) {
+                return None;
+            }
+
+            // Wrap around the antimeridian.
+            let lon = match center.lon + dx * width {
+                lon if lon > LON_MAX => lon - 360.0,
+                lon if lon < LON_MIN => lon + 360.0,
+                lon => lon,
+            };
+
+            let cell = encode(Point { lon, lat }, step);
+            Some((cell << shift, (cell + 1) << shift))
+        })
+        .collect();
+
+    ranges.sort_unstable();
+    ranges.dedup();
+    ranges
+}
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Extension, Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::net::TcpListener;
+
+use crate::{
+    auth::NOAUTH,
+    command::{parse_expr, Command, Expr},
+    server::{handle_command, handle_pipe, Db, Session},
+};
+
+type Response = (StatusCode, Json<Value>);
+
+/// Serves the gateway on a listener [`server::start`](crate::server::start) already bound,
+/// so a port that's taken stops the server like the tcp port does.
+pub async fn start(listener: TcpListener, db: Db) {
+    let router = Router::new()
+        .route("/keys", get(list_keys))
+        .route("/keys/{id}", get(get_key).put(set_key).delete(delete_key))
+        .route("/command", post(raw_command))
+        .route_layer(middleware::from_fn_with_state(db.clone(), authenticate))
+        .with_state(db.clone());
+
+    let shutdown = async move { db.stopping().await };
+
+    if let Err(err) = axum::serve(listener, router).with_graceful_shutdown(shutdown).await {
+        println!("Http gateway stopped: {err}");
+    }
+}
+
+async fn get_key(
+    State(db): State<Db>,
+    Extension(mut session): Extension<Session>,
+    Path(id): Path<String>
+) -> Response {
+    respond(handle_command(Command::Get { id }, &db, &mut session).await)
+}
+
+async fn set_key(
+    State(db): State<Db>,
+    Extension(mut session): Extension<Session>,
+    Path(id): Path<String>,
+    data: String
+) -> Response {
+    respond(handle_command(Command::Set { id, data }, &db, &mut session).await)
+}
+
+async fn delete_key(
+    State(db): State<Db>,
+    Extension(mut session): Extension<Session>,
+    Path(id): Path<String>
+) -> Response {
+    let command = Command::Delete { expr: Expr::ID(id) };
+    respond(handle_command(command, &db, &mut session).await)
+}
+
+async fn list_keys(
+    State(db): State<Db>,
+    Extension(mut session): Extension<Session>,
+    Query(query): Query<HashMap<String, String>>
+) -> Response {
+    let expr = query.get("expr").map(String::as_str).unwrap_or("-1");
+    let namespace = query.get("ns").cloned();
+    let command = match parse_expr(expr) {
+        Ok(expr) => Command::List { expr, namespace },
+        Err(err) => Command::Invalid { reason: err.to_string() },
+    };
+
+    respond(handle_command(command, &db, &mut session).await)
+}
+
+async fn raw_command(
+    State(db): State<Db>,
+    Extension(mut session): Extension<Session>,
+    body: String
+) -> Response {
+    let line = body.trim();
+    if line.is_empty() {
+        return respond(Err("Empty command".to_string()));
+    }
+
+    let inputs = line.split("~>").map(str::trim).collect::<Vec<&str>>();
+
+    respond(handle_pipe(inputs, &db, &mut session).await)
+}
+
+/// Requests authenticate with `Authorization: Bearer <password>` for the default user or
+/// `Authorization: Basic` for acl users. Without the header they run as the default user, if it has no password.
+async fn authenticate(State(db): State<Db>, mut request: Request, next: Next) -> axum::response::Response {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    let user = match header {
+        Some(header) => match credentials(header) {
+            Some((username, password)) => db.auth.authenticate(username.as_deref(), &password),
+            None => Err("Invalid Authorization header".to_string()),
+        },
+        None => db.auth.default_user().ok_or(NOAUTH.to_string()),
+    };
+
+    match user {
+        Ok(user) => {
+            request.extensions_mut().insert(Session { user: Some(user), database: 0 });
+            next.run(request).await
+        }
+        Err(err) => error(StatusCode::UNAUTHORIZED, err).into_response(),
+    }
+}
+
+fn credentials(header: &str) -> Option<(Option<String>, String)> {
+    if let Some(password) = header.strip_prefix("Bearer ") {
+        return Some((None, password.to_string()));
+    }
+
+    let decoded = String::from_utf8(STANDARD.decode(header.strip_prefix("Basic ")?).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+
+    Some((Some(username.to_string()), password.to_string()))
+}
+
+fn respond(result: Result<String, String>) -> Response {
+    match result {
+        Ok(response) => (StatusCode::OK, Json(json!({ "status": "ok", "response": response }))),
+        Err(err) => error(status(&err), err),
+    }
+}
+
+fn error(status: StatusCode, err: String) -> Response {
+    (status, Json(json!({ "status": "err", "response": err })))
+}
+
+/// The status of an error reply, by its prefix. Anything unknown is the request's fault.
+fn status(err: &str) -> StatusCode {
+    match err.split(' ').next().unwrap_or_default() {
+        "NOAUTH" | "WRONGPASS" => StatusCode::UNAUTHORIZED,
+        "NOPERM" => StatusCode::FORBIDDEN,
+        "OOM" => StatusCode::INSUFFICIENT_STORAGE,
+        "WRONGTYPE" => StatusCode::CONFLICT,
+        "Cannot" if err.starts_with("Cannot find ") || err.starts_with("Cannot delete ") => StatusCode::NOT_FOUND,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
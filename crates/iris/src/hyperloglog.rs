@@ -0,0 +1,119 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// How many bits of the hash pick the register, like redis uses.
+const PRECISION: u32 = 14;
+
+/// 16384 registers, for a standard error of 1.04 / sqrt(16384) = 0.81%.
+const REGISTERS: usize = 1 << PRECISION;
+
+/// Estimates how many distinct elements were added in a fixed 16kb, however many there are.
+///
+/// Every element hashes to a register, which keeps the longest run of leading zeros seen in the rest of
+/// the hash. Long runs are rare, so together they tell how many distinct hashes went by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog { registers: vec![0; REGISTERS] }
+    }
+}
+
+impl HyperLogLog {
+    /// Adds an element. Returns whether the estimate may have changed.
+    pub fn add(&mut self, element: &str) -> bool {
+        // Not `RandomState`, an element has to land in the same register every time.
+        let mut hasher = DefaultHasher::new();
+        element.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let register = (hash >> (64 - PRECISION)) as usize;
+        // The low bit stops the count at the end of the 50 bits left.
+        let rank = ((hash << PRECISION) | 1 << (PRECISION - 1)).leading_zeros() as u8 + 1;
+
+        match rank > self.registers[register] {
+            true => {
+                self.registers[register] = rank;
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Folds another estimate into this one, as if its elements had been added here.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, rank) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*rank);
+        }
+    }
+
+    /// The estimator from Ertl's "New cardinality estimation algorithms for HyperLogLog sketches", which
+    /// redis uses too. Unlike the original one it stays unbiased from a handful of elements up, without
+    /// switching to linear counting for small counts.
+    pub fn count(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let q = 64 - PRECISION as usize;
+
+        // How many registers hold each rank.
+        let mut histogram = vec![0u32; q + 2];
+        for rank in &self.registers {
+            histogram[*rank as usize] += 1;
+        }
+
+        let mut z = m * tau((m - histogram[q + 1] as f64) / m);
+        for k in (1..=q).rev() {
+            z = 0.5 * (z + histogram[k] as f64);
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+
+        (m * m / (2.0 * std::f64::consts::LN_2 * z)).round() as u64
+    }
+
+    /// The bytes the registers take up.
+    pub fn len(&self) -> usize {
+        self.registers.len()
+    }
+}
+
+/// Corrects for the registers still at zero.
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let (mut y, mut z) = (1.0, x);
+    loop {
+        x *= x;
+        let last = z;
+        z += x * y;
+        y += y;
+
+        if z == last {
+            return z;
+        }
+    }
+}
+
+/// Corrects for the registers at the highest rank.
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let (mut y, mut z) = (1.0, 1.0 - x);
+    loop {
+        x = x.sqrt();
+        let last = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+
+        if z == last {
+            return z / 3.0;
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    ops::Bound,
+    str::FromStr,
+};
+
+use parking_lot::RwLock;
+
+use crate::{
+    json::{self, Path},
+    memory::Entry,
+    pattern,
+    search::{self, TextIndex},
+    value::Value,
+    vector::{Metric, Vector, VectorIndex},
+};
+
+/// A value an index sorts by. Only strings, numbers and booleans are indexed, keys holding anything
+/// else at the path are left out.
+#[derive(Debug, Clone)]
+pub enum Key {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Key {
+    fn from_json(value: &serde_json::Value) -> Option<Key> {
+        match value {
+            serde_json::Value::Bool(bool) => Some(Key::Bool(*bool)),
+            serde_json::Value::Number(number) => number.as_f64().map(Key::Number),
+            serde_json::Value::String(string) => Some(Key::String(string.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Orders the types so every one of them is a single run of the index: booleans, numbers, then strings.
+    fn rank(&self) -> u8 {
+        match self {
+            Key::Bool(_) => 0,
+            Key::Number(_) => 1,
+            Key::String(_) => 2,
+        }
+    }
+
+    /// Where the run of this key's type starts.
+    fn first(&self) -> Bound<Key> {
+        match self {
+            Key::Bool(_) => Bound::Included(Key::Bool(false)),
+            Key::Number(_) => Bound::Included(Key::Number(f64::NEG_INFINITY)),
+            Key::String(_) => Bound::Included(Key::String(String::new())),
+        }
+    }
+
+    /// Where the run of this key's type ends.
+    fn last(&self) -> Bound<Key> {
+        match self {
+            Key::Bool(_) => Bound::Included(Key::Bool(true)),
+            Key::Number(_) => Bound::Included(Key::Number(f64::INFINITY)),
+            Key::String(_) => Bound::Unbounded,
+        }
+    }
+}
+
+/// Parses a JSON scalar, or takes the text as a string so `FIND by_city Palermo` doesn't need quotes.
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<Key, String> {
+        match json::parse(key) {
+            Ok(value) => Key::from_json(&value).ok_or(format!("Invalid value {key:?}, only strings, numbers and booleans are indexed")),
+            Err(_) => Ok(Key::String(key.to_string())),
+        }
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        match (self, other) {
+            (Key::Bool(a), Key::Bool(b)) => a.cmp(b),
+            (Key::Number(a), Key::Number(b)) => a.total_cmp(b),
+            (Key::String(a), Key::String(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Key) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+/// What `FIND` looks for: one value, or an inclusive range of them where a missing end is open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Exact(Key),
+    Range(Option<Key>, Option<Key>),
+}
+
+/// `<value>` or `<min>..<max>`. A quoted string is always a value, so `"a..b"` matches literally.
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Query, String> {
+        let (start, end) = match query.split_once("..") {
+            Some(range) if json::parse(query).is_err() => range,
+            _ => return Ok(Query::Exact(query.parse()?)),
+        };
+
+        let parse = |side: &str| match side.is_empty() {
+            true => Ok(None),
+            false => side.parse::<Key>().map(Some),
+        };
+
+        match (parse(start)?, parse(end)?) {
+            (None, None) => Err(format!("Invalid range {query:?}, it needs at least one end")),
+            (Some(start), Some(end)) if start.rank() != end.rank() => {
+                Err(format!("Invalid range {query:?}, both ends need the same type"))
+            }
+            (start, end) => Ok(Query::Range(start, end)),
+        }
+    }
+}
+
+/// What `INDEX CREATE` indexes the keys by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// The value at a JSON path, for `FIND`.
+    Value(Path),
+    /// The words of the value, or of the part of a document at a path, for `SEARCH`.
+    Text(Option<Path>),
+    /// Vectors of some dimensions for `VSEARCH`, searched through a HNSW graph when `approximate` is set.
+    Vector { dimensions: usize, metric: Metric, approximate: bool },
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Value(path) => write!(f, "FIELD {path}"),
+            Field::Text(None) => write!(f, "TEXT"),
+            Field::Text(Some(path)) => write!(f, "TEXT FIELD {path}"),
+            Field::Vector { dimensions, metric, approximate: false } => write!(f, "VECTOR {dimensions} {metric}"),
+            Field::Vector { dimensions, metric, approximate: true } => write!(f, "VECTOR {dimensions} {metric} HNSW"),
+        }
+    }
+}
+
+/// The ids of the keys sorted by the value at a path in them.
+#[derive(Debug, Default)]
+struct ValueIndex {
+    ids: BTreeMap<Key, BTreeSet<String>>,
+    /// What every indexed id is filed under, to find it again when it changes.
+    keys: HashMap<String, Key>,
+}
+
+impl ValueIndex {
+    fn add(&mut self, id: &str, value: &Value, path: &Path) {
+        let key = match value {
+            Value::Json(document) => json::lookup(document.root(), path).and_then(Key::from_json),
+            // Strings set with `SET` are indexed too when they hold JSON.
+            Value::String(data) => json::parse(data).ok().and_then(|root| Key::from_json(json::lookup(&root, path)?)),
+            _ => None,
+        };
+
+        if let Some(key) = key {
+            self.ids.entry(key.clone()).or_default().insert(id.to_string());
+            self.keys.insert(id.to_string(), key);
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(key) = self.keys.remove(id) {
+            if let Some(ids) = self.ids.get_mut(&key) {
+                ids.remove(id);
+
+                if ids.is_empty() {
+                    self.ids.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn find(&self, query: &Query) -> Vec<&String> {
+        let (start, end) = match query {
+            Query::Exact(key) => (Bound::Included(key.clone()), Bound::Included(key.clone())),
+            Query::Range(Some(start), Some(end)) if start > end => return vec![],
+            Query::Range(Some(start), Some(end)) => (Bound::Included(start.clone()), Bound::Included(end.clone())),
+            Query::Range(Some(start), None) => (Bound::Included(start.clone()), start.last()),
+            Query::Range(None, Some(end)) => (end.first(), Bound::Included(end.clone())),
+            Query::Range(None, None) => return vec![],
+        };
+
+        self.ids.range((start, end)).flat_map(|(_, ids)| ids).collect()
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    Value(ValueIndex),
+    Text(TextIndex),
+    Vector(VectorIndex),
+}
+
+impl Kind {
+    fn add(&mut self, field: &Field, id: &str, value: &Value) {
+        match (self, field) {
+            (Kind::Value(index), Field::Value(path)) => index.add(id, value, path),
+            (Kind::Text(index), Field::Text(path)) => {
+                if let Some(text) = search::text(value, path.as_ref()) {
+                    index.add(id, &text);
+                }
+            }
+            (Kind::Vector(index), Field::Vector { .. }) => {
+                if let Some(vector) = Vector::of(value) {
+                    index.add(id, vector);
+                }
+            }
+            _ => unreachable!("the kind of an index follows its field"),
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        match self {
+            Kind::Value(index) => index.remove(id),
+            Kind::Text(index) => index.remove(id),
+            Kind::Vector(index) => index.remove(id),
+        }
+    }
+}
+
+/// The keys matching `pattern`, indexed by `field`. Every index has its own lock, so keys
+/// of different indexes, or of none, are filed without waiting on each other.
+#[derive(Debug)]
+struct Index {
+    pattern: String,
+    field: Field,
+    kind: RwLock<Kind>,
+}
+
+/// The secondary indexes of a database, kept up to date on every insert and remove like
+/// [`crate::namespace::Namespaces`], so `FIND` and `SEARCH` never have to scan the keyspace.
+#[derive(Debug, Default)]
+pub struct Indexes {
+    indexes: BTreeMap<String, Index>,
+}
+
+impl Indexes {
+    /// Adds an index over the keys matching `pattern`, starting with the `entries` already there.
+    pub fn create<'a>(
+        &mut self,
+        name: &str,
+        pattern: &str,
+        field: Field,
+        entries: impl Iterator<Item = (&'a String, &'a Entry)>,
+    ) -> Result<(), String> {
+        if self.indexes.contains_key(name) {
+            return Err(format!("Index {name} already exists"));
+        }
+
+        let mut kind = match field {
+            Field::Value(_) => Kind::Value(ValueIndex::default()),
+            Field::Text(_) => Kind::Text(TextIndex::default()),
+            Field::Vector { dimensions, metric, approximate } => Kind::Vector(VectorIndex::new(dimensions, metric, approximate)),
+        };
+
+        for (id, entry) in entries.filter(|(id, _)| pattern::matches(pattern, id)) {
+            kind.add(&field, id, &entry.value);
+        }
+
+        self.indexes.insert(name.to_string(), Index { pattern: pattern.to_string(), field, kind: RwLock::new(kind) });
+        Ok(())
+    }
+
+    /// Removes an index. Returns whether there was one.
+    pub fn delete(&mut self, name: &str) -> bool {
+        self.indexes.remove(name).is_some()
+    }
+
+    /// The ids whose value matches `query`, sorted by value and then id.
+    pub fn find(&self, name: &str, query: &Query) -> Result<Vec<String>, String> {
+        match &*self.get(name)?.kind.read() {
+            Kind::Value(index) => Ok(index.find(query).into_iter().cloned().collect()),
+            Kind::Text(_) => Err(format!("Index {name} is a text index, use SEARCH")),
+            Kind::Vector(_) => Err(format!("Index {name} is a vector index, use VSEARCH")),
+        }
+    }
+
+    /// The ids matching a text `query`, most relevant first.
+    pub fn search(&self, name: &str, query: &search::Query) -> Result<Vec<(String, f64)>, String> {
+        match &*self.get(name)?.kind.read() {
+            Kind::Text(index) => Ok(index.search(query).into_iter().map(|(id, score)| (id.to_owned(), score)).collect()),
+            Kind::Value(_) => Err(format!("Index {name} isn't a text index, use FIND")),
+            Kind::Vector(_) => Err(format!("Index {name} isn't a text index, use VSEARCH")),
+        }
+    }
+
+    /// The `k` nearest ids `visible` allows to `query` with their distance, nearest first.
+    pub fn vsearch(
+        &self,
+        name: &str,
+        query: Vector,
+        k: usize,
+        exact: bool,
+        visible: &dyn Fn(&str) -> bool,
+    ) -> Result<Vec<(String, f32)>, String> {
+        match &*self.get(name)?.kind.read() {
+            Kind::Vector(index) => Ok(index
+                .search(query, k, exact, visible)?
+                .into_iter()
+                .map(|(id, distance)| (id.to_owned(), distance))
+                .collect()),
+            Kind::Value(_) => Err(format!("Index {name} isn't a vector index, use FIND")),
+            Kind::Text(_) => Err(format!("Index {name} isn't a vector index, use SEARCH")),
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<&Index, String> {
+        self.indexes.get(name).ok_or(format!("Cannot find index {name}"))
+    }
+
+    /// Every index as `<name> ON <pattern> <field>`, sorted by name.
+    pub fn list(&self) -> Vec<String> {
+        self.indexes
+            .iter()
+            .map(|(name, index)| format!("{name} ON {} {}", index.pattern, index.field))
+            .collect()
+    }
+
+    /// Files a key that was set under its new value in every index it matches, or drops it from them
+    /// when it was removed. Only the indexes whose pattern matches are locked.
+    pub fn set(&self, id: &str, value: Option<&Value>) {
+        for index in self.indexes.values().filter(|index| pattern::matches(&index.pattern, id)) {
+            let mut kind = index.kind.write();
+            kind.remove(id);
+
+            if let Some(value) = value {
+                kind.add(&index.field, id, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::{fmt, str::FromStr};
+
+use serde_json::{Number, Value};
+
+/// One step of a [`Path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    /// Negative indexes count from the end of the array.
+    Index(i64),
+}
+
+/// Where in a document a command looks, like `$.user.tags[0]` or `$["odd.key"]`. `$` is the whole document.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Path(Vec<Segment>);
+
+impl Path {
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(path: &str) -> Result<Path, String> {
+        let invalid = || format!("Invalid path {path:?}, expected one like $.user.tags[0]");
+
+        let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+        let mut segments = vec![];
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix("[\"") {
+                let end = after.find("\"]").ok_or_else(invalid)?;
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end + 2..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                segments.push(Segment::Index(after[..end].parse().map_err(|_| invalid())?));
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Path(segments))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+
+        self.0.iter().try_for_each(|segment| match segment {
+            Segment::Key(key) if key.contains(|c: char| !c.is_alphanumeric() && c != '_') => write!(f, "[{key:?}]"),
+            Segment::Key(key) => write!(f, ".{key}"),
+            Segment::Index(index) => write!(f, "[{index}]"),
+        })
+    }
+}
+
+/// A JSON value, always valid since it's parsed on write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    root: Value,
+    /// The length of the serialized document, kept up to date so `maxmemory` doesn't have to serialize it.
+    size: usize,
+}
+
+impl Document {
+    pub fn new(root: Value) -> Document {
+        let size = root.to_string().len();
+        Document { root, size }
+    }
+
+    pub fn get(&self, path: &Path) -> Result<&Value, String> {
+        lookup(&self.root, path).ok_or_else(|| missing(path))
+    }
+
+    /// Sets the value at `path`. Objects get new keys, but arrays only take indexes they already have.
+    pub fn set(&mut self, path: &Path, value: Value) -> Result<(), String> {
+        let result = self.change(path, |parent, last| match (parent, last) {
+            (Value::Object(object), Segment::Key(key)) => {
+                object.insert(key.to_string(), value);
+                Ok(())
+            }
+            (Value::Array(array), Segment::Index(index)) => {
+                let index = resolve(array.len(), *index).ok_or_else(|| missing(path))?;
+                array[index] = value;
+                Ok(())
+            }
+            _ => Err(missing(path)),
+        });
+
+        self.size = self.root.to_string().len();
+        result
+    }
+
+    /// Removes the value at `path`. Returns whether there was one.
+    pub fn delete(&mut self, path: &Path) -> bool {
+        let deleted = self
+            .change(path, |parent, last| match (parent, last) {
+                (Value::Object(object), Segment::Key(key)) => Ok(object.remove(key).is_some()),
+                (Value::Array(array), Segment::Index(index)) => match resolve(array.len(), *index) {
+                    Some(index) => Ok({
+                        array.remove(index);
+                        true
+                    }),
+                    None => Ok(false),
+                },
+                _ => Ok(false),
+            })
+            .unwrap_or(false);
+
+        self.size = self.root.to_string().len();
+        deleted
+    }
+
+    /// Appends to the array at `path`. Returns its new length.
+    pub fn append(&mut self, path: &Path, values: Vec<Value>) -> Result<usize, String> {
+        let array = match self.get_mut(path)? {
+            Value::Array(array) => array,
+            other => return Err(format!("Path {path} is {}, not an array", kind(other))),
+        };
+
+        array.extend(values);
+        let len = array.len();
+
+        self.size = self.root.to_string().len();
+        Ok(len)
+    }
+
+    /// Adds to the number at `path`, staying an integer while both are. Returns the new number.
+    pub fn increment(&mut self, path: &Path, by: &Number) -> Result<Number, String> {
+        let number = match self.get_mut(path)? {
+            Value::Number(number) => number,
+            other => return Err(format!("Path {path} is {}, not a number", kind(other))),
+        };
+
+        let sum = match (number.as_i64(), by.as_i64()) {
+            (Some(a), Some(b)) => a.checked_add(b).map(Number::from),
+            _ => Number::from_f64(number.as_f64().unwrap_or(0.0) + by.as_f64().unwrap_or(0.0)),
+        };
+
+        *number = sum.ok_or(format!("Incrementing {path} would overflow"))?;
+        let sum = number.clone();
+
+        self.size = self.root.to_string().len();
+        Ok(sum)
+    }
+
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// The bytes of the serialized document.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn get_mut(&mut self, path: &Path) -> Result<&mut Value, String> {
+        path.0
+            .iter()
+            .try_fold(&mut self.root, |value, segment| child_mut(value, segment))
+            .ok_or_else(|| missing(path))
+    }
+
+    /// Runs `change` on the parent of the last segment, which has to exist.
+    fn change<T>(
+        &mut self,
+        path: &Path,
+        change: impl FnOnce(&mut Value, &Segment) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let (last, parents) = path.0.split_last().ok_or("The root can't be changed in place")?;
+        let parent = parents
+            .iter()
+            .try_fold(&mut self.root, |value, segment| child_mut(value, segment))
+            .ok_or_else(|| missing(path))?;
+
+        change(parent, last)
+    }
+}
+
+/// The value at `path` inside `value`, if there is one.
+pub fn lookup<'a>(value: &'a Value, path: &Path) -> Option<&'a Value> {
+    path.0.iter().try_fold(value, |value, segment| child(value, segment))
+}
+
+fn child<'a>(value: &'a Value, segment: &Segment) -> Option<&'a Value> {
+    match (value, segment) {
+        (Value::Object(object), Segment::Key(key)) => object.get(key),
+        (Value::Array(array), Segment::Index(index)) => array.get(resolve(array.len(), *index)?),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, segment: &Segment) -> Option<&'a mut Value> {
+    match (value, segment) {
+        (Value::Object(object), Segment::Key(key)) => object.get_mut(key),
+        (Value::Array(array), Segment::Index(index)) => {
+            let index = resolve(array.len(), *index)?;
+            array.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+/// An index of a `len` long array, with negative ones counting from the end.
+fn resolve(len: usize, index: i64) -> Option<usize> {
+    let index = match index < 0 {
+        true => len as i64 + index,
+        false => index,
+    };
+
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn missing(path: &Path) -> String {
+    format!("Path {path} does not exist")
+}
+
+/// Parses a value written with `JSET` or `JARRAPPEND`, so only valid documents are ever stored.
+pub fn parse(value: &str) -> Result<Value, String> {
+    serde_json::from_str(value).map_err(|err| format!("Invalid JSON: {err}"))
+}
+
+/// Parses whitespace separated values, like the ones `JARRAPPEND` takes.
+pub fn parse_many(values: &str) -> Result<Vec<Value>, String> {
+    serde_json::Deserializer::from_str(values)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|err| format!("Invalid JSON: {err}"))
+}
@@ -1,35 +1,102 @@
-use std::process;
-use clap::{arg, error::ErrorKind, value_parser, Command};
+use std::{net::IpAddr, process};
+use clap::{arg, value_parser, ArgAction, Command};
 
 mod client;
 mod server;
 mod command;
+mod http;
+mod ws;
+mod events;
+mod pattern;
+mod auth;
+mod tls;
+mod config;
+mod memory;
+mod store;
+mod namespace;
+mod value;
+mod bitmap;
+mod hyperloglog;
+mod stream;
+mod geo;
+mod json;
+mod index;
+mod search;
+mod vector;
+mod script;
 
 #[tokio::main]
 async fn main() {
-    let mut command = command();
-
-    match command.clone().get_matches().subcommand() {
+    match command().get_matches().subcommand() {
         Some(("server", sub)) => {
-            let port = sub.get_one::<u32>("port").unwrap();
-            let format = match sub.get_one::<String>("format").unwrap() {
-                f if f == "default" || f == "json" => f.to_string(),
-                _ => {
-                    let error = command.error(ErrorKind::InvalidValue, "Invalid format value.\nvalid values: ('default', 'json')");
-                    println!("{error}");
-
+            let settings = match config::load(sub) {
+                Ok(settings) => settings,
+                Err(err) => {
+                    println!("{err}");
                     process::exit(1);
                 }
             };
-            let debug = sub.get_one::<bool>("debug").unwrap();
 
-            server::start(format!("127.0.0.1:{port}").as_str(), format, debug.to_owned()).await;
+            let users = match &settings.aclfile {
+                Some(path) => match auth::load_acl_file(path) {
+                    Ok(users) => users,
+                    Err(err) => {
+                        println!("{err}");
+                        process::exit(1);
+                    }
+                },
+                None => vec![],
+            };
+
+            let tls = match (&settings.tls_cert, &settings.tls_key) {
+                (Some(cert), Some(key)) => match tls::acceptor(cert, key, settings.tls_ca_cert.as_deref()) {
+                    Ok(acceptor) => Some(acceptor),
+                    Err(err) => {
+                        println!("{err}");
+                        process::exit(1);
+                    }
+                },
+                _ => None,
+            };
+
+            let auth = auth::Auth::new(settings.requirepass.clone(), users);
+
+            server::start(settings, auth, tls).await;
         }
         Some(("client", sub)) => {
             let host = sub.get_one::<String>("host").unwrap();
-            let port = sub.get_one::<u32>("port").unwrap();
+            let port = sub.get_one::<u16>("port").unwrap();
+
+            let username = sub.get_one::<String>("user").cloned();
+            let password = sub.get_one::<String>("password").cloned();
 
-            client::start(format!("{host}:{port}").as_str()).await;
+            let tls = match sub.get_one::<bool>("tls").unwrap() {
+                true => {
+                    let ca = sub.get_one::<String>("tls-ca-cert").map(String::as_str);
+                    let identity = sub
+                        .get_one::<String>("tls-cert")
+                        .zip(sub.get_one::<String>("tls-key"))
+                        .map(|(cert, key)| (cert.as_str(), key.as_str()));
+
+                    match tls::connector(ca, identity) {
+                        Ok(connector) => Some(connector),
+                        Err(err) => {
+                            println!("{err}");
+                            process::exit(1);
+                        }
+                    }
+                }
+                false => None,
+            };
+
+            let target = match sub.get_one::<String>("socket") {
+                Some(path) => client::Target::Unix { path: path.to_string() },
+                None => client::Target::Tcp { host: host.to_string(), port: *port, tls },
+            };
+
+            let database = sub.get_one::<usize>("db").copied();
+
+            client::start(target, username, password, database).await;
         }
         _ => unreachable!(),
     }
@@ -43,19 +110,71 @@ fn command() -> Command {
             Command::new("server")
                 .about("Start the in-memory server")
                 .args(vec![
-                    arg!(-p --port <number> "The port the server will run on")
-                        .value_parser(value_parser!(u32))
-                        .default_value("3000")
+                    arg!(-c --config <path> "Load settings from a toml file, flags override it")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(-b --bind <address> "The addresses the server listens on, ipv4 or ipv6 [default: 127.0.0.1]")
+                        .value_parser(value_parser!(IpAddr))
+                        .num_args(1..)
+                        .action(ArgAction::Append)
                         .required(false),
-                    arg!(-f --format <string> "The format of the server response ('default', 'json')")
+                    arg!(-p --port <number> "The port the server will run on [default: 3000]")
+                        .value_parser(value_parser!(u16))
+                        .required(false),
+                    arg!(-f --format <string> "The format of the server response ('default', 'json') [default: default]")
                         .value_name("json")
                         .value_parser(value_parser!(String))
-                        .default_value("default")
                         .required(false),
                     arg!(-d --debug "The port the server will run on")
                         .value_parser(value_parser!(bool))
                         .default_value("false")
                         .required(false),
+                    arg!(--"http-port" <number> "Also serve the HTTP/REST gateway on this port")
+                        .value_parser(value_parser!(u16))
+                        .required(false),
+                    arg!(--"ws-port" <number> "Also serve the command protocol over websocket on this port")
+                        .value_parser(value_parser!(u16))
+                        .required(false),
+                    arg!(--requirepass <string> "Require clients to AUTH with this password")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--passfile <path> "Read the required password from the first line of a file")
+                        .value_parser(value_parser!(String))
+                        .conflicts_with("requirepass")
+                        .required(false),
+                    arg!(--aclfile <path> "Load users and their permissions from a toml file")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"unix-socket" <path> "Also accept connections on this unix socket (use --port 0 for only the socket)")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"unix-socket-perm" <octal> "Permission bits of the unix socket file, e.g. 770")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"tls-cert" <path> "Serve tls with this PEM certificate chain")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"tls-key" <path> "The PEM private key of the tls certificate")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"tls-ca-cert" <path> "Require clients to present a certificate signed by this PEM ca (mTLS)")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--databases <number> "How many databases clients can SELECT from [default: 16]")
+                        .value_parser(value_parser!(usize))
+                        .required(false),
+                    arg!(--"namespace-delimiter" <string> "Separates the namespaces in key ids [default: :]")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--maxmemory <bytes> "Limit the memory keys take up, e.g. 100mb [default: 0, no limit]")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"maxmemory-policy" <policy> "What to do at the limit ('noeviction', 'allkeys-lru', 'allkeys-lfu', 'volatile-ttl', 'allkeys-random') [default: noeviction]")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(--"script-time-limit" <ms> "Stop scripts running longer than this, other commands on keys wait for them [default: 5000, 0 for no limit]")
+                        .value_parser(value_parser!(u64))
+                        .required(false),
                 ]),
         )
         .subcommand(
@@ -67,9 +186,39 @@ fn command() -> Command {
                         .default_value("127.0.0.1")
                         .required(false),
                     arg!(-p --port <number> "The port the client connect to")
-                        .value_parser(value_parser!(u32))
+                        .value_parser(value_parser!(u16))
                         .default_value("3000")
                         .required(false),
+                    arg!(-s --socket <path> "Connect to a unix socket instead of host and port")
+                        .value_parser(value_parser!(String))
+                        .conflicts_with("tls")
+                        .required(false),
+                    arg!(-a --password <string> "Authenticate with this password after connecting")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                    arg!(-u --user <string> "Authenticate as this acl user")
+                        .value_parser(value_parser!(String))
+                        .requires("password")
+                        .required(false),
+                    arg!(-n --db <index> "SELECT this database after connecting")
+                        .value_parser(value_parser!(usize))
+                        .required(false),
+                    arg!(--tls "Connect over tls")
+                        .value_parser(value_parser!(bool))
+                        .default_value("false")
+                        .required(false),
+                    arg!(--"tls-ca-cert" <path> "Trust this PEM ca instead of the webpki roots")
+                        .value_parser(value_parser!(String))
+                        .requires("tls")
+                        .required(false),
+                    arg!(--"tls-cert" <path> "Present this PEM client certificate (mTLS)")
+                        .value_parser(value_parser!(String))
+                        .requires("tls-key")
+                        .required(false),
+                    arg!(--"tls-key" <path> "The PEM private key of the client certificate")
+                        .value_parser(value_parser!(String))
+                        .requires("tls-cert")
+                        .required(false),
                 ]),
         )
 }
@@ -0,0 +1,137 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use crate::value::Value;
+
+/// A logical clock ticking on every access, so the least recently used key is the one with the lowest tick.
+static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// A stored value and the access stats eviction picks victims by.
+#[derive(Debug)]
+pub struct Entry {
+    pub value: Value,
+    last_access: AtomicU64,
+    hits: AtomicU32,
+}
+
+impl Entry {
+    /// What a key costs besides its id and data: the map slot, the value headers and the access stats.
+    /// It doesn't have to be exact, just close enough that `maxmemory` tracks the real footprint.
+    pub const OVERHEAD: usize = 96;
+
+    pub fn new(value: Value) -> Entry {
+        Entry {
+            value,
+            last_access: AtomicU64::new(CLOCK.fetch_add(1, Ordering::Relaxed)),
+            hits: AtomicU32::new(1),
+        }
+    }
+
+    /// Records a read. Only needs a shared reference, so readers don't have to lock exclusively.
+    pub fn touch(&self) {
+        self.last_access.store(CLOCK.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        let _ = self.hits.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |hits| hits.checked_add(1));
+    }
+
+    /// The approximate bytes a key with this entry takes up.
+    pub fn size(&self, id: &str) -> usize {
+        id.len() + self.value.len() + Entry::OVERHEAD
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// Refuse writes that would go over the limit.
+    #[default]
+    NoEviction,
+    /// Evict the least recently used key.
+    AllKeysLru,
+    /// Evict the least frequently used key.
+    AllKeysLfu,
+    /// Evict the key closest to expiring. Keys don't expire in iris yet, so none is volatile and writes
+    /// over the limit are refused like with `noeviction`.
+    VolatileTtl,
+    /// Evict any key.
+    AllKeysRandom,
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Policy, String> {
+        match policy {
+            "noeviction" => Ok(Policy::NoEviction),
+            "allkeys-lru" => Ok(Policy::AllKeysLru),
+            "allkeys-lfu" => Ok(Policy::AllKeysLfu),
+            "volatile-ttl" => Ok(Policy::VolatileTtl),
+            "allkeys-random" => Ok(Policy::AllKeysRandom),
+            _ => Err(format!(
+                "Invalid maxmemory-policy {policy:?}, valid values: ('noeviction', 'allkeys-lru', 'allkeys-lfu', 'volatile-ttl', 'allkeys-random')"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Policy::NoEviction => "noeviction",
+            Policy::AllKeysLru => "allkeys-lru",
+            Policy::AllKeysLfu => "allkeys-lfu",
+            Policy::VolatileTtl => "volatile-ttl",
+            Policy::AllKeysRandom => "allkeys-random",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl Policy {
+    /// Where an entry stands in the eviction order, lowest goes first.
+    /// `None` when the policy doesn't allow evicting it.
+    pub fn rank(&self, entry: &Entry) -> Option<(u64, u64)> {
+        let last_access = entry.last_access.load(Ordering::Relaxed);
+
+        match self {
+            // Only keys with an expiry are volatile, and none have one yet.
+            Policy::NoEviction | Policy::VolatileTtl => None,
+            Policy::AllKeysLru => Some((last_access, 0)),
+            Policy::AllKeysLfu => Some((entry.hits.load(Ordering::Relaxed) as u64, last_access)),
+            Policy::AllKeysRandom => Some((random(), 0)),
+        }
+    }
+}
+
+/// A fresh random number, out of the keys the standard library seeds its hashers with.
+pub fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Parses a byte count like `1048576`, `512kb`, `100mb` or `2gb`. `0` means no limit.
+pub fn parse_bytes(value: &str) -> Result<usize, String> {
+    let lower = value.trim().to_ascii_lowercase();
+
+    let (number, unit) = match lower.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => lower.split_at(index),
+        None => (lower.as_str(), ""),
+    };
+
+    let multiplier: usize = match unit {
+        "" | "b" => 1,
+        "kb" => 1 << 10,
+        "mb" => 1 << 20,
+        "gb" => 1 << 30,
+        _ => return Err(format!("Invalid maxmemory {value:?}, expected bytes like 1048576, 512kb, 100mb or 2gb")),
+    };
+
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or(format!("Invalid maxmemory {value:?}, expected bytes like 1048576, 512kb, 100mb or 2gb"))
+}
@@ -0,0 +1,44 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// The keys of every namespace, kept up to date on every insert and remove so namespace
+/// commands never have to scan the whole keyspace.
+///
+/// Namespaces nest: with the `:` delimiter, `service:entity:id` is in both `service` and `service:entity`.
+#[derive(Debug, Default)]
+pub struct Namespaces {
+    keys: HashMap<String, BTreeSet<String>>,
+}
+
+impl Namespaces {
+    pub fn add(&mut self, id: &str, delimiter: &str) {
+        for namespace in of(id, delimiter) {
+            self.keys.entry(namespace.to_string()).or_default().insert(id.to_string());
+        }
+    }
+
+    pub fn remove(&mut self, id: &str, delimiter: &str) {
+        for namespace in of(id, delimiter) {
+            if let Some(keys) = self.keys.get_mut(namespace) {
+                keys.remove(id);
+
+                if keys.is_empty() {
+                    self.keys.remove(namespace);
+                }
+            }
+        }
+    }
+
+    /// Every namespace and its keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &BTreeSet<String>)> {
+        self.keys.iter()
+    }
+
+    pub fn keys(&self, namespace: &str) -> impl Iterator<Item = &String> {
+        self.keys.get(namespace).into_iter().flatten()
+    }
+}
+
+/// The namespaces of an id, outermost first. Ids without the delimiter have none.
+fn of<'a>(id: &'a str, delimiter: &'a str) -> impl Iterator<Item = &'a str> {
+    id.match_indices(delimiter).map(|(index, _)| &id[..index])
+}
@@ -0,0 +1,23 @@
+/// Matches an id against a glob pattern where `*` matches any run of characters,
+/// so `user:*` matches every id starting with `user:`.
+pub fn matches(pattern: &str, id: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    if parts.len() == 1 {
+        return pattern == id;
+    }
+
+    let mut rest = match id.strip_prefix(parts[0]) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(parts[parts.len() - 1])
+}
@@ -0,0 +1,218 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+use rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, ParseError, Scope, AST};
+use tokio::runtime::Handle;
+
+use crate::{
+    command::{parse_parts, Command},
+    server::{handle_response, Db, Session},
+};
+
+/// What `call(...)` runs, with the command and its args.
+type Call = dyn Fn(Vec<Dynamic>) -> Result<String, Box<EvalAltResult>> + Send + Sync;
+
+pub const NOSCRIPT: &str = "NOSCRIPT No matching script, use SCRIPT LOAD";
+
+/// The longest script `EVAL` and `SCRIPT LOAD` compile, in bytes.
+const MAX_SCRIPT_LEN: usize = 64 << 10;
+
+/// How many compiled scripts are kept, the oldest one is dropped for a new one past that.
+const MAX_SCRIPTS: usize = 1000;
+
+/// What a running script may build up, so it can't run out of memory before the time limit stops it.
+const MAX_STRING_SIZE: usize = 16 << 20;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 100_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+
+/// Splits a script in braces off the front of what `command` was sent with, returning the script
+/// with its braces and the rest. Braces inside strings and characters don't count.
+pub fn split<'a>(command: &str, input: &'a str) -> Result<(&'a str, &'a str), String> {
+    if !input.starts_with('{') {
+        return Err(format!("{command:?} requires a Script in braces"));
+    }
+
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (at, char) in input.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if char == '\\' => escaped = true,
+            Some(end) if char == end => quote = None,
+            Some(_) => {}
+            None => match char {
+                '"' | '\'' | '`' => quote = Some(char),
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Ok(input.split_at(at + 1));
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    Err("Invalid script, missing }".to_string())
+}
+
+/// The id `EVALSHA` runs a script by, the sha1 of its source.
+pub fn sha(script: &str) -> String {
+    sha1_smol::Sha1::from(script).digest().to_string()
+}
+
+/// The last [`MAX_SCRIPTS`] scripts `EVAL` or `SCRIPT LOAD` compiled, by sha, oldest first.
+#[derive(Default)]
+pub struct Scripts(RwLock<IndexMap<String, Arc<AST>>>);
+
+impl Scripts {
+    /// Compiles a script unless it was compiled before, returning its sha.
+    pub fn load(&self, script: &str) -> Result<(String, Arc<AST>), String> {
+        let sha = sha(script);
+
+        if let Some(ast) = self.get(&sha) {
+            return Ok((sha, ast));
+        }
+
+        if script.len() > MAX_SCRIPT_LEN {
+            return Err(format!("Invalid script, longer than {MAX_SCRIPT_LEN} bytes"));
+        }
+
+        let ast = Arc::new(engine(Arc::new(|_| Ok(String::new()))).compile(script).map_err(|err| format!("Invalid script, {err}"))?);
+
+        let mut scripts = self.0.write().unwrap();
+        if scripts.len() >= MAX_SCRIPTS {
+            scripts.shift_remove_index(0);
+        }
+        scripts.insert(sha.clone(), Arc::clone(&ast));
+
+        Ok((sha, ast))
+    }
+
+    pub fn get(&self, sha: &str) -> Option<Arc<AST>> {
+        self.0.read().unwrap().get(sha).cloned()
+    }
+}
+
+/// Runs a script with `KEYS` and `ARGS` in scope. Every `call(...)` goes through
+/// [`handle_response`] as the session, the caller keeps other commands out while it runs.
+///
+/// Writes a script made before failing or running out of time stay.
+pub fn run(db_clone: &Db, session: &Session, ast: &AST, keys: Vec<String>, args: Vec<String>) -> Result<String, String> {
+    let limit = db_clone.settings.read().unwrap().script_time_limit;
+    let started = Instant::now();
+
+    let (db_clone, session, handle) = (Arc::clone(db_clone), session.clone(), Handle::current());
+    let mut engine = engine(Arc::new(move |args| {
+        let parts: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        call(&db_clone, &session, &handle, &parts).map_err(|err| err.into())
+    }));
+    engine.on_progress(move |_| {
+        (limit > 0 && started.elapsed() > Duration::from_millis(limit)).then_some(Dynamic::UNIT)
+    });
+
+    let mut scope = Scope::new();
+    scope.push("KEYS", keys.into_iter().map(Dynamic::from).collect::<rhai::Array>());
+    scope.push("ARGS", args.into_iter().map(Dynamic::from).collect::<rhai::Array>());
+
+    let result = tokio::task::block_in_place(|| engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast));
+
+    match result {
+        Ok(result) => Ok(reply(result)),
+        Err(err) => Err(error(*err, limit)),
+    }
+}
+
+/// An engine where `call(...)` takes any number of args and runs them with `run`.
+/// Rhai keeps `call` for function pointers, so it's custom syntax rather than a function.
+fn engine(run: Arc<Call>) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|_| {});
+    engine
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+    engine.register_custom_syntax_with_state_raw("call", parse_call, false, move |context, inputs, _| {
+        let args = inputs
+            .iter()
+            .map(|input| context.eval_expression_tree(input))
+            .collect::<Result<Vec<Dynamic>, _>>()?;
+
+        run(args).map(Dynamic::from)
+    });
+
+    engine
+}
+
+/// Parses `call(<expr>, ...)` one symbol at a time.
+fn parse_call(symbols: &[ImmutableString], look_ahead: &str, _: &mut Dynamic) -> Result<Option<ImmutableString>, ParseError> {
+    let next = match (symbols.last().map(ImmutableString::as_str), look_ahead) {
+        (Some("call"), _) => "(",
+        (Some("("), ")") | (Some("$expr$"), ")") => ")",
+        (Some("(") | Some(","), _) => "$expr$",
+        (Some("$expr$"), _) => ",",
+        _ => return Ok(None),
+    };
+
+    Ok(Some(next.into()))
+}
+
+/// Runs one command for `call(...)`, authorized like it was sent on the connection.
+/// Each arg is one part of the command as it is, never split again.
+fn call(db_clone: &Db, session: &Session, handle: &Handle, parts: &[String]) -> Result<String, String> {
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+
+    // `EVAL` and `SCRIPT LOAD` only parse from a whole line, so they're refused by name.
+    let command = match parts.first() {
+        Some(&name @ ("EVAL" | "SCRIPT")) => return Err(format!("{name} can't be called from a script")),
+        _ => parse_parts(&parts),
+    };
+
+    match &command {
+        Command::EvalSha { .. }
+        | Command::Auth { .. }
+        | Command::Select { .. }
+        | Command::Shutdown { .. }
+        | Command::Subscribe { .. }
+        | Command::Unsubscribe { .. } => return Err(format!("{} can't be called from a script", command.name())),
+        _ => {}
+    }
+
+    if let Some(user) = &session.user {
+        user.authorize(&command)?;
+    }
+
+    handle.block_on(handle_response(command, db_clone, session))
+}
+
+/// Turns what a script evaluated to into a reply, `OK` for nothing.
+fn reply(result: Dynamic) -> String {
+    if result.is_unit() {
+        return "OK".to_string();
+    }
+
+    match result.is_array() {
+        true => format!("{:?}", result.cast::<rhai::Array>().into_iter().map(reply).collect::<Vec<String>>()),
+        false => result.to_string(),
+    }
+}
+
+fn error(err: EvalAltResult, limit: u64) -> String {
+    match err {
+        EvalAltResult::ErrorTerminated(..) => format!("Script exceeded the time limit of {limit}ms"),
+        EvalAltResult::ErrorInFunctionCall(_, _, err, _) => error(*err, limit),
+        EvalAltResult::ErrorRuntime(value, _) => value.to_string(),
+        err => format!("Script error: {err}"),
+    }
+}
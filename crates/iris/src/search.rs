@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::{
+    json::{self, Path},
+    value::Value,
+};
+
+/// How fast repeating a term stops adding to the score, the usual BM25 value.
+const K1: f64 = 1.2;
+
+/// How much longer documents are held back for containing more terms, the usual BM25 value.
+const B: f64 = 0.75;
+
+/// Splits text into lowercase words, the terms both documents and queries are matched by.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase)
+}
+
+/// The text of a value to index: strings as they are, and every string in a JSON document, or in the part of
+/// it at `path`. Strings holding JSON are searched like documents once there's a `path`.
+pub fn text(value: &Value, path: Option<&Path>) -> Option<String> {
+    let mut strings = vec![];
+
+    match (value, path) {
+        (Value::String(data), None) => return Some(data.to_string()),
+        (Value::String(data), Some(path)) => collect(json::lookup(&json::parse(data).ok()?, path)?, &mut strings),
+        (Value::Json(document), None) => collect(document.root(), &mut strings),
+        (Value::Json(document), Some(path)) => collect(json::lookup(document.root(), path)?, &mut strings),
+        _ => return None,
+    }
+
+    Some(strings.join(" "))
+}
+
+fn collect(value: &serde_json::Value, strings: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(string) => strings.push(string.to_string()),
+        serde_json::Value::Array(values) => values.iter().for_each(|value| collect(value, strings)),
+        serde_json::Value::Object(object) => object.values().for_each(|value| collect(value, strings)),
+        _ => {}
+    }
+}
+
+/// What `SEARCH` looks for. Words next to each other all have to match, `OR` takes either side, `NOT` or a
+/// leading `-` leaves matches out and a trailing `*` matches every word starting with what's before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Term(String),
+    Prefix(String),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Query, String> {
+        let tokens: Vec<String> = query
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+        let parsed = parser.or()?;
+
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(token) => Err(format!("Invalid query, unexpected {token:?}")),
+        }
+    }
+}
+
+/// Reads a query by precedence: `OR` binds loosest, then the implicit `AND`, then `NOT`.
+struct Parser<'a> {
+    tokens: &'a [String],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        self.position += 1;
+        self.tokens.get(self.position - 1).map(String::as_str)
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut queries = vec![self.and()?];
+
+        while self.peek() == Some("OR") {
+            self.next();
+            queries.push(self.and()?);
+        }
+
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => Query::Or(queries),
+        })
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut queries = vec![self.not()?];
+
+        while !matches!(self.peek(), None | Some("OR") | Some(")")) {
+            if self.peek() == Some("AND") {
+                self.next();
+            }
+
+            queries.push(self.not()?);
+        }
+
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => Query::And(queries),
+        })
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        match self.peek() {
+            Some("NOT") => {
+                self.next();
+                Ok(Query::Not(Box::new(self.not()?)))
+            }
+            Some(token) if token.len() > 1 && token.starts_with('-') => {
+                let word = token[1..].to_string();
+                self.next();
+                Ok(Query::Not(Box::new(word_query(&word)?)))
+            }
+            _ => self.word(),
+        }
+    }
+
+    fn word(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some("(") => {
+                let query = self.or()?;
+
+                match self.next() {
+                    Some(")") => Ok(query),
+                    _ => Err("Invalid query, missing )".to_string()),
+                }
+            }
+            Some(token @ (")" | "AND" | "OR")) => Err(format!("Invalid query, unexpected {token:?}")),
+            Some(word) => word_query(word),
+            None => Err("Invalid query, it ends too early".to_string()),
+        }
+    }
+}
+
+/// A word of the query, split like documents are so `e-mail` looks for both halves.
+fn word_query(word: &str) -> Result<Query, String> {
+    let (word, prefix) = match word.strip_suffix('*') {
+        Some(word) => (word, true),
+        None => (word, false),
+    };
+
+    let mut terms: Vec<String> = tokenize(word).collect();
+    let last = match terms.pop() {
+        Some(last) if prefix => Query::Prefix(last),
+        Some(last) => Query::Term(last),
+        None => return Err(format!("Invalid query, {word:?} has no letters or digits")),
+    };
+
+    Ok(match terms.is_empty() {
+        true => last,
+        false => Query::And(terms.into_iter().map(Query::Term).chain([last]).collect()),
+    })
+}
+
+/// What an indexed key needs to be taken out again.
+#[derive(Debug)]
+struct Indexed {
+    length: usize,
+    terms: Vec<String>,
+}
+
+/// An inverted index from every word to the keys containing it, ranked with BM25.
+#[derive(Debug, Default)]
+pub struct TextIndex {
+    /// Every term with how often it's in each key.
+    terms: BTreeMap<String, HashMap<String, u32>>,
+    keys: HashMap<String, Indexed>,
+    /// The terms of every key together, for the average length.
+    length: usize,
+}
+
+impl TextIndex {
+    pub fn add(&mut self, id: &str, text: &str) {
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for term in tokenize(text) {
+            *counts.entry(term).or_default() += 1;
+        }
+
+        if counts.is_empty() {
+            return;
+        }
+
+        let length = counts.values().sum::<u32>() as usize;
+        let terms = counts.keys().cloned().collect();
+
+        for (term, count) in counts {
+            self.terms.entry(term).or_default().insert(id.to_string(), count);
+        }
+
+        self.keys.insert(id.to_string(), Indexed { length, terms });
+        self.length += length;
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        let indexed = match self.keys.remove(id) {
+            Some(indexed) => indexed,
+            None => return,
+        };
+
+        for term in indexed.terms {
+            if let Some(ids) = self.terms.get_mut(&term) {
+                ids.remove(id);
+
+                if ids.is_empty() {
+                    self.terms.remove(&term);
+                }
+            }
+        }
+
+        self.length -= indexed.length;
+    }
+
+    /// The keys matching `query`, most relevant first.
+    pub fn search(&self, query: &Query) -> Vec<(&String, f64)> {
+        let mut terms = vec![];
+        self.positive(query, &mut terms);
+
+        let mut found: Vec<(&String, f64)> = self
+            .matches(query)
+            .into_iter()
+            .map(|id| (id, terms.iter().map(|term| self.score(term, id)).sum()))
+            .collect();
+
+        found.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        found
+    }
+
+    fn matches(&self, query: &Query) -> HashSet<&String> {
+        match query {
+            Query::Term(term) => self.terms.get(term).into_iter().flat_map(|ids| ids.keys()).collect(),
+            Query::Prefix(prefix) => self.prefixed(prefix).flat_map(|(_, ids)| ids.keys()).collect(),
+            Query::And(queries) => queries
+                .iter()
+                .map(|query| self.matches(query))
+                .reduce(|all, matches| all.intersection(&matches).copied().collect())
+                .unwrap_or_default(),
+            Query::Or(queries) => queries.iter().flat_map(|query| self.matches(query)).collect(),
+            Query::Not(query) => {
+                let excluded = self.matches(query);
+                self.keys.keys().filter(|id| !excluded.contains(id)).collect()
+            }
+        }
+    }
+
+    /// The terms that count towards the score, the ones not under a `NOT`.
+    fn positive<'a>(&'a self, query: &'a Query, terms: &mut Vec<&'a String>) {
+        match query {
+            Query::Term(term) => terms.push(term),
+            Query::Prefix(prefix) => terms.extend(self.prefixed(prefix).map(|(term, _)| term)),
+            Query::And(queries) | Query::Or(queries) => queries.iter().for_each(|query| self.positive(query, terms)),
+            Query::Not(_) => {}
+        }
+    }
+
+    fn prefixed<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (&'a String, &'a HashMap<String, u32>)> {
+        let prefix = prefix.to_string();
+        self.terms.range(prefix.clone()..).take_while(move |(term, _)| term.starts_with(&prefix))
+    }
+
+    /// BM25: rare terms count for more, and so do short keys that repeat the term.
+    fn score(&self, term: &str, id: &str) -> f64 {
+        let (ids, indexed, count) = match (self.terms.get(term), self.keys.get(id)) {
+            (Some(ids), Some(indexed)) => match ids.get(id) {
+                Some(count) => (ids, indexed, *count as f64),
+                None => return 0.0,
+            },
+            _ => return 0.0,
+        };
+
+        let keys = self.keys.len() as f64;
+        let idf = (1.0 + (keys - ids.len() as f64 + 0.5) / (ids.len() as f64 + 0.5)).ln();
+
+        let average = self.length as f64 / keys;
+        let norm = K1 * (1.0 - B + B * indexed.length as f64 / average);
+
+        idf * count * (K1 + 1.0) / (count + norm)
+    }
+}
@@ -1,37 +1,198 @@
 use indoc::indoc;
-use std::{collections::HashMap, io, process, sync::Arc};
+use std::{
+    fs,
+    net::SocketAddr,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
 use serde_json::json;
 use tokio::{
-    io::AsyncWriteExt,
-    net::{TcpListener, TcpStream},
-    sync::Mutex,
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, UnixListener},
+    signal::unix::{signal, SignalKind},
+    sync::{broadcast, watch},
+    task::JoinSet,
+    time::{self, Duration},
+};
+
+use tokio_rustls::TlsAcceptor;
+
+use crate::{
+    auth::{Auth, User, NOAUTH},
+    bitmap,
+    command::{parse_command, Center, Command, Expr},
+    config::{Settings, NAMES},
+    events::Event,
+    geo::{self, Added, Geo},
+    hyperloglog::HyperLogLog,
+    json::Document,
+    memory::Entry,
+    store::Shards,
+    stream::{self, Fields},
+    http, pattern,
+    script::{self, Scripts, NOSCRIPT},
+    tls::Stream,
+    value::{self, Value},
+    ws,
 };
 
-use crate::command::{parse_command, Command, Expr};
+pub struct Store {
+    /// The logical databases connections pick with `SELECT`, all sharing one `maxmemory`.
+    pub databases: Vec<Shards>,
+    /// The approximate bytes the keys of every database take up together.
+    pub used: Arc<AtomicUsize>,
+    pub events: broadcast::Sender<Event>,
+    pub auth: Auth,
+    pub settings: RwLock<Settings>,
+    /// Scripts compiled by `EVAL` and `SCRIPT LOAD`, by sha.
+    pub scripts: Scripts,
+    /// Held for writing while a script runs and for reading by every command that touches keys,
+    /// so scripts run atomically. Those commands wait for a running script on every connection.
+    pub scripting: tokio::sync::RwLock<()>,
+    /// Flips to `true` once the server starts shutting down.
+    pub shutdown: watch::Sender<bool>,
+}
+
+impl Store {
+    pub fn format(&self) -> String {
+        self.settings.read().unwrap().format.clone()
+    }
+
+    pub fn debug(&self) -> bool {
+        self.settings.read().unwrap().debug
+    }
+
+    /// Inserts an entry, evicting keys from any database by `maxmemory-policy` first if it would go over `maxmemory`.
+    pub fn insert(&self, database: usize, id: String, entry: Entry) -> Result<(), String> {
+        self.make_room(database, &id, entry.size(&id))?;
+        self.databases[database].insert(id, entry);
+
+        Ok(())
+    }
+
+    /// Evicts keys until `size` more bytes for `id` fit under `maxmemory`, or fails with `OOM`.
+    /// Call it before locking any shard, since eviction locks them one at a time.
+    ///
+    /// Concurrent writes can make the limit overshoot by a few entries.
+    pub fn make_room(&self, database: usize, id: &str, size: usize) -> Result<(), String> {
+        let (maxmemory, policy) = {
+            let settings = self.settings.read().unwrap();
+            (settings.maxmemory, settings.maxmemory_policy)
+        };
+
+        let items = &self.databases[database];
+
+        // The entry being replaced frees its own bytes, so only count what's left of the rest.
+        let fits = || {
+            let replaced = items.read(id).get(id).map_or(0, |old| old.size(id));
+            maxmemory == 0 || self.used.load(Ordering::Relaxed).saturating_sub(replaced) + size <= maxmemory
+        };
+
+        // Evicts from a pool of sampled keys lowest rank first, and only samples again once it runs dry.
+        let mut pool: Vec<((u64, u64), usize, String)> = vec![];
+
+        while !fits() {
+            if pool.is_empty() {
+                pool = self
+                    .databases
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(from, items)| {
+                        let skip = (from == database).then_some(id);
+                        items.candidates(policy, skip).into_iter().map(move |(rank, victim)| (rank, from, victim))
+                    })
+                    .collect();
+                pool.sort_unstable_by(|a, b| b.cmp(a));
+                pool.dedup();
+            }
+
+            let (_, from, victim) = pool.pop().ok_or(OOM.to_string())?;
+            let shards = &self.databases[from];
+
+            if shards.remove(&mut shards.write(&victim), &victim).is_some() {
+                let _ = self.events.send(Event::Delete { database: from, id: victim });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops accepting connections and lets the open ones finish their current command.
+    pub fn shutdown(&self, reason: &str) {
+        if !self.shutdown.send_replace(true) {
+            println!("Shutting down ({reason}).");
+        }
+    }
+
+    /// Resolves once the server starts shutting down.
+    pub async fn stopping(&self) {
+        let _ = self.shutdown.subscribe().wait_for(|stopping| *stopping).await;
+    }
+
+    /// Re-reads the config file, see [`Settings::reload`].
+    pub fn reload(&self) -> Result<String, String> {
+        let mut settings = self.settings.write().unwrap();
+        let password = settings.requirepass.clone();
+        let restart = settings.reload()?;
+
+        if settings.requirepass != password {
+            self.auth.set_password(settings.requirepass.clone());
+        }
+
+        match restart.is_empty() {
+            true => Ok("OK".to_string()),
+            false => Ok(format!("OK, restart needed for {}", restart.join(", "))),
+        }
+    }
+}
+
+pub type Db = Arc<Store>;
+
+/// The longest value `SETRANGE` grows a key to.
+pub const MAX_VALUE_LEN: usize = 512 << 20;
+
+pub const OOM: &str = "OOM command not allowed when used memory > 'maxmemory'";
+
+/// How long open connections get to finish their commands once the server shuts down.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Per-connection state, threaded through every command the connection sends.
+#[derive(Clone)]
+pub struct Session {
+    pub user: Option<Arc<User>>,
+    /// The database picked with `SELECT`.
+    pub database: usize,
+}
+
+impl Session {
+    pub fn new(db: &Db) -> Session {
+        Session { user: db.auth.default_user(), database: 0 }
+    }
+
+    pub fn can_access(&self, id: &str) -> bool {
+        self.user.as_ref().is_some_and(|user| user.can_access(id))
+    }
+}
 
 macro_rules! respond {
     ($stream:expr, $response:expr) => {{
-        $stream.write(format!("{}\n", $response).as_bytes()).await.unwrap();
+        $stream.write_all(format!("{}\n", $response).as_bytes()).await.unwrap();
     }};
 }
 
 macro_rules! respond_ok {
     ($stream:expr, $format:expr, $response:expr) => {
-        match $format.as_str() {
-            "default" => respond!($stream, format!("ok {}", $response)),
-            "json" => respond!($stream, json!({ "status": "ok", "response": $response })),
-            _ => unreachable!()
-        }
+        respond!($stream, format_response(&$format, "ok", &$response))
     };
 }
 
 macro_rules! respond_err {
     ($stream:expr, $format:expr, $response:expr) => {
-        match $format.as_str() {
-            "default" => respond!($stream, format!("err {}", $response)),
-            "json" => respond!($stream, json!({ "status": "err", "response": $response })),
-            _ => unreachable!()
-        }
+        respond!($stream, format_response(&$format, "err", &$response))
     };
 }
 
@@ -43,16 +204,70 @@ macro_rules! debug {
     };
 }
 
-pub async fn start(addr: &str, format: String, debug: bool) {
-    let listener = match TcpListener::bind(addr).await {
-        Ok(listener) => listener,
-        Err(err) => {
-            println!("Failed to start the server: {err}");
-            process::exit(1);
+/// Everything the server accepts connections on, all sharing one store.
+/// There is always at least one tcp address or a unix socket.
+pub struct Listeners {
+    pub tcp: Vec<SocketAddr>,
+    pub unix: Option<UnixSocket>,
+    pub http: Vec<SocketAddr>,
+    pub ws: Vec<SocketAddr>,
+}
+
+pub struct UnixSocket {
+    pub path: String,
+    /// Permission bits applied to the socket file, e.g. `0o770`.
+    pub mode: Option<u32>,
+}
+
+pub async fn start(settings: Settings, auth: Auth, tls: Option<TlsAcceptor>) {
+    let listeners = settings.listeners();
+
+    let mut tcp_listeners = vec![];
+
+    for addr in &listeners.tcp {
+        match TcpListener::bind(addr).await {
+            Ok(listener) => tcp_listeners.push(listener),
+            Err(err) => {
+                println!("Failed to start the server on {addr}: {err}");
+                process::exit(1);
+            }
         }
-    };
+    }
+
+    let mut http_listeners = vec![];
+
+    for addr in &listeners.http {
+        match TcpListener::bind(addr).await {
+            Ok(listener) => http_listeners.push(listener),
+            Err(err) => {
+                println!("Failed to start the http gateway on {addr}: {err}");
+                process::exit(1);
+            }
+        }
+    }
 
-    let local_addr = &listener.local_addr().unwrap();
+    let mut ws_listeners = vec![];
+
+    for addr in &listeners.ws {
+        match TcpListener::bind(addr).await {
+            Ok(listener) => ws_listeners.push(listener),
+            Err(err) => {
+                println!("Failed to start the websocket server on {addr}: {err}");
+                process::exit(1);
+            }
+        }
+    }
+
+    let unix_listener = match &listeners.unix {
+        Some(socket) => match bind_unix(socket) {
+            Ok(listener) => Some(listener),
+            Err(err) => {
+                println!("Failed to start the server: {err}");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
 
     println!(
         indoc! {"
@@ -64,62 +279,230 @@ pub async fn start(addr: &str, format: String, debug: bool) {
             ▄▄█▄▄   █     ▄▄█▄▄  ▀▄▄▄▀
 
             Server has started.
-            • version:  {}
-            • host:     http://{}
-            • port:     {}
-        "},
-        env!("CARGO_PKG_VERSION"),
-        local_addr,
-        local_addr.port()
+            • version:  {}"},
+        env!("CARGO_PKG_VERSION")
     );
 
-    let db: Arc<Mutex<HashMap<String, String>>> = Arc::new(Mutex::new(HashMap::new()));
+    for listener in &tcp_listeners {
+        println!("• host:     http://{}", listener.local_addr().unwrap());
+    }
+
+    if let Some(listener) = tcp_listeners.first() {
+        println!("• port:     {}", listener.local_addr().unwrap().port());
+    }
+
+    if let Some(socket) = &listeners.unix {
+        println!("• socket:   {}", socket.path);
+    }
+
+    for listener in &http_listeners {
+        println!("• http:     http://{}", listener.local_addr().unwrap());
+    }
+
+    for listener in &ws_listeners {
+        println!("• ws:       ws://{}", listener.local_addr().unwrap());
+    }
+
+    if let Some(path) = &settings.path {
+        println!("• config:   {path}");
+    }
+
+    println!();
+
+    let used = Arc::new(AtomicUsize::new(0));
+    let db: Db = Arc::new(Store {
+        databases: (0..settings.databases).map(|_| Shards::new(Arc::clone(&used), &settings.namespace_delimiter)).collect(),
+        used,
+        events: broadcast::channel(1024).0,
+        auth,
+        settings: RwLock::new(settings),
+        scripts: Scripts::default(),
+        scripting: tokio::sync::RwLock::new(()),
+        shutdown: watch::channel(false).0,
+    });
+
+    let mut servers = JoinSet::new();
+
+    for listener in http_listeners {
+        servers.spawn(http::start(listener, Arc::clone(&db)));
+    }
+
+    for listener in ws_listeners {
+        servers.spawn(ws::start(listener, Arc::clone(&db)));
+    }
+
+    for listener in tcp_listeners {
+        servers.spawn(accept_tcp(listener, Arc::clone(&db), tls.clone()));
+    }
+
+    if let Some(listener) = unix_listener {
+        servers.spawn(accept_unix(listener, Arc::clone(&db)));
+    }
+
+    tokio::spawn(reload_on_hangup(Arc::clone(&db)));
+    tokio::spawn(shutdown_on_signal(Arc::clone(&db)));
+
+    db.stopping().await;
+
+    // Every server stops accepting and waits for its connections, up to the deadline.
+    let drained = time::timeout(DRAIN_TIMEOUT, async {
+        while servers.join_next().await.is_some() {}
+    })
+    .await
+    .is_ok();
+
+    if let Some(socket) = &listeners.unix {
+        let _ = fs::remove_file(&socket.path);
+    }
+
+    if !drained {
+        println!("Connections did not finish within {}s, closing them.", DRAIN_TIMEOUT.as_secs());
+        process::exit(1);
+    }
+
+    println!("Server has stopped.");
+    process::exit(0);
+}
+
+async fn shutdown_on_signal(db: Db) {
+    let (mut interrupts, mut terminates) = match (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) {
+        (Ok(interrupts), Ok(terminates)) => (interrupts, terminates),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("Failed to listen for SIGINT and SIGTERM: {err}");
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = interrupts.recv() => db.shutdown("SIGINT"),
+        _ = terminates.recv() => db.shutdown("SIGTERM"),
+    }
+}
+
+async fn reload_on_hangup(db: Db) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(err) => {
+            println!("Failed to listen for SIGHUP: {err}");
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        match db.reload() {
+            Ok(response) => println!("Config reloaded: {response}"),
+            Err(err) => println!("Failed to reload the config: {err}"),
+        }
+    }
+}
+
+fn bind_unix(socket: &UnixSocket) -> Result<UnixListener, String> {
+    // A socket file left behind by a previous run would make the bind fail.
+    if fs::metadata(&socket.path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        fs::remove_file(&socket.path).map_err(|err| format!("Failed to remove the stale socket: {err}"))?;
+    }
+
+    let listener = UnixListener::bind(&socket.path).map_err(|err| err.to_string())?;
+
+    if let Some(mode) = socket.mode {
+        fs::set_permissions(&socket.path, fs::Permissions::from_mode(mode))
+            .map_err(|err| format!("Failed to set the socket permissions: {err}"))?;
+    }
+
+    Ok(listener)
+}
+
+async fn accept_tcp(listener: TcpListener, db: Db, tls: Option<TlsAcceptor>) {
+    let mut connections = JoinSet::new();
 
     loop {
-        let mut stream = match listener.accept().await {
-            Ok((stream, _)) => stream,
-            Err(err) => {
-                println!("Failed to get the client: {err}");
-                return;
-            }
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    println!("Failed to get the client: {err}");
+                    break;
+                }
+            },
+            _ = db.stopping() => break,
         };
 
-        let format_clone = format.clone();
         let db_clone = Arc::clone(&db);
+        let tls_clone = tls.clone();
 
-        tokio::spawn(async move {
-            handle_connection(&mut stream, db_clone, format_clone, debug).await;
+        connections.spawn(async move {
+            match tls_clone {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => handle_connection(stream, db_clone).await,
+                    Err(err) => debug!(format!("TLS handshake failed: {err}"), db_clone.debug()),
+                },
+                None => handle_connection(stream, db_clone).await,
+            }
         });
     }
+
+    drop(listener);
+    while connections.join_next().await.is_some() {}
+}
+
+async fn accept_unix(listener: UnixListener, db: Db) {
+    let mut connections = JoinSet::new();
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    println!("Failed to get the client: {err}");
+                    break;
+                }
+            },
+            _ = db.stopping() => break,
+        };
+
+        connections.spawn(handle_connection(stream, Arc::clone(&db)));
+    }
+
+    drop(listener);
+    while connections.join_next().await.is_some() {}
 }
 
-async fn handle_connection(
-    stream: &mut TcpStream,
-    db_clone: Arc<Mutex<HashMap<String, String>>>,
-    format: String,
-    debug: bool
-) {
+async fn handle_connection(stream: impl Stream, db_clone: Db) {
+    let mut session = Session::new(&db_clone);
+    let (reader, mut stream) = io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
     loop {
-        let mut buffer = [0; 4096];
-        let line = match stream.try_read(&mut buffer) {
-            Ok(0) => {
-                debug!("Connection closed.", debug);
+        // A command that was already read runs to the end, shutdown only interrupts the wait for the next one.
+        let line = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    debug!("Connection closed.", db_clone.debug());
+                    break;
+                }
+                Err(err) => {
+                    println!("Failed to read: {err}.");
+                    break;
+                }
+            },
+            _ = db_clone.stopping() => {
+                let notice = format_response(&db_clone.format(), "event", "shutdown");
+                let _ = stream.write_all(format!("{notice}\n").as_bytes()).await;
                 break;
             }
-            Ok(bytes) => std::str::from_utf8(&buffer[..bytes]).unwrap().trim(),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
-            Err(err) => {
-                println!("Failed to read: {err}.");
-                continue;
-            }
         };
+        let line = line.trim();
 
         if line.is_empty() {
             continue;
         }
 
         let inputs = line.split("~>").map(str::trim).collect::<Vec<&str>>();
-        match handle_pipe(inputs, &db_clone).await {
+        let result = handle_pipe(inputs, &db_clone, &mut session).await;
+        let format = db_clone.format();
+
+        match result {
             Ok(response) => respond_ok!(stream, format, response),
             Err(err) => respond_err!(stream, format, err)
         }
@@ -132,21 +515,22 @@ async fn handle_connection(
             "},
                 &line,
             ),
-            debug
+            db_clone.debug()
         );
     }
 }
 
-async fn handle_pipe<'a>(
+pub async fn handle_pipe(
     inputs: Vec<&str>,
-    db_clone: &Arc<Mutex<HashMap<String, String>>>
+    db_clone: &Db,
+    session: &mut Session
 ) -> Result<String, String> {
     let mut prev = String::new();
 
     for input in inputs {
         let command = parse_command(format!("{} {}", input, prev));
 
-        match handle_response(command, &db_clone).await {
+        match handle_command(command, db_clone, session).await {
             Ok(resp) => prev = resp,
             Err(err) => return Err(err)
         }
@@ -155,50 +539,115 @@ async fn handle_pipe<'a>(
     Ok(prev)
 }
 
-async fn handle_response<'a>(
+/// Authenticates and authorizes a command for the session before running it.
+pub async fn handle_command(
     command: Command,
-    db_clone: &Arc<Mutex<HashMap<String, String>>>
+    db_clone: &Db,
+    session: &mut Session
 ) -> Result<String, String> {
+    if let Command::Auth { username, password } = &command {
+        return match db_clone.auth.authenticate(username.as_deref(), password) {
+            Ok(user) => {
+                session.user = Some(user);
+                Ok("OK".to_string())
+            }
+            Err(err) => Err(err),
+        };
+    }
+
+    match &session.user {
+        Some(user) => user.authorize(&command)?,
+        None => return Err(NOAUTH.to_string()),
+    }
+
+    if let Command::Select { database } = command {
+        if database >= db_clone.databases.len() {
+            return Err("DB index is out of range".to_string());
+        }
+
+        session.database = database;
+        return Ok("OK".to_string());
+    }
+
+    if let Command::Eval { .. } | Command::EvalSha { .. } = command {
+        let _running = db_clone.scripting.write().await;
+        return handle_response(command, db_clone, session).await;
+    }
+
+    // Commands that don't touch keys can't see a script halfway, so they don't wait for one.
+    let _running = match command.touches_keys() {
+        true => Some(db_clone.scripting.read().await),
+        false => None,
+    };
+    handle_response(command, db_clone, session).await
+}
+
+/// Deletes every key of `database` the session can access.
+fn flush(db_clone: &Db, session: &Session, database: usize) {
+    let mut items = db_clone.databases[database].write_all();
+    let ids: Vec<String> = items
+        .iter()
+        .filter(|(id, _)| session.can_access(id))
+        .map(|(id, _)| id.to_owned())
+        .collect();
+
+    for id in ids {
+        items.remove(&id);
+        let _ = db_clone.events.send(Event::Delete { database, id });
+    }
+}
+
+pub async fn handle_response(
+    command: Command,
+    db_clone: &Db,
+    session: &Session
+) -> Result<String, String> {
+    let items = &db_clone.databases[session.database];
+
     match command {
         Command::Ping {  } => {
             Ok("PONG".to_string())
         }
         Command::Get { id } => {
-            let db = db_clone.lock().await;
+            let db = items.read(&id);
             let result = match db.get(&id) {
-                Some(item) => item,
+                Some(entry) => entry,
                 None => return Err(format!("Cannot find item with an id of {id}"))
             };
 
-            Ok(result.to_owned())
+            result.touch();
+            Ok(result.value.as_str()?.to_owned())
         }
-        Command::List { expr } => {
-            let db = db_clone.lock().await;
+        Command::List { expr, namespace } => {
+            let db = items.read_all();
+            let entries = db.entries(namespace.as_deref());
 
             match expr {
                 Expr::Number(mut count) => {
                     if count == -1 {
-                        count = db.len() as i32;
+                        count = entries.len() as i32;
                     }
 
-                    let result: Vec<(String, String)> = db
-                        .iter()
+                    let result: Vec<(String, String)> = entries
+                        .into_iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .take(count as usize)
-                        .map(|(id, data)| (id.to_owned(), data.to_owned()))
+                        .map(|(id, entry)| (id.to_owned(), entry.value.to_string()))
                         .collect();
 
                     Ok(format!("{:?}", result))
                 }
                 Expr::Range(start, mut end) => {
                     if end < 0 {
-                        end = db.len() as i32;
+                        end = entries.len() as i32;
                     }
 
-                    let result: Vec<(String, String)> = db
-                        .iter()
+                    let result: Vec<(String, String)> = entries
+                        .into_iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .skip(start as usize)
                         .take((end + 1) as usize)
-                        .map(|(id, data)| (id.to_owned(), data.to_owned()))
+                        .map(|(id, entry)| (id.to_owned(), entry.value.to_string()))
                         .collect();
 
                     Ok(format!("{:?}", result))
@@ -206,33 +655,36 @@ async fn handle_response<'a>(
                 _ => Err("This is expression is not allowed".to_string())
             }
         }
-        Command::Count { expr } => {
-            let db = db_clone.lock().await;
+        Command::Count { expr, namespace } => {
+            let db = items.read_all();
+            let entries = db.entries(namespace.as_deref());
 
             match expr {
                 Expr::Number(mut count) => {
                     if count == -1 {
-                        count = db.len() as i32;
+                        count = entries.len() as i32;
                     }
 
-                    let result: Vec<(String, String)> = db
-                        .iter()
+                    let result: Vec<(String, String)> = entries
+                        .into_iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .take(count as usize)
-                        .map(|(id, data)| (id.to_owned(), data.to_owned()))
+                        .map(|(id, entry)| (id.to_owned(), entry.value.to_string()))
                         .collect();
 
                     Ok(format!("{}", result.len()))
                 }
                 Expr::Range(start, mut end) => {
                     if end < 0 {
-                        end = db.len() as i32;
+                        end = entries.len() as i32;
                     }
 
-                    let result: Vec<(String, String)> = db
-                        .iter()
+                    let result: Vec<(String, String)> = entries
+                        .into_iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .skip(start as usize)
                         .take((end + 1) as usize)
-                        .map(|(id, data)| (id.clone(), data.clone()))
+                        .map(|(id, entry)| (id.clone(), entry.value.to_string()))
                         .collect();
 
                     Ok(format!("{}", result.len()))
@@ -241,23 +693,25 @@ async fn handle_response<'a>(
             }
         }
         Command::Set { id, data } => {
-            let mut db = db_clone.lock().await;
-
-            db.insert(id.to_owned(), data.to_owned());
+            db_clone.insert(session.database, id.to_owned(), Entry::new(Value::String(data)))?;
+            let _ = db_clone.events.send(Event::Set { database: session.database, id: id.to_owned() });
 
             Ok(id.to_owned())
         }
         Command::Delete { expr } => {
-            let mut db = db_clone.lock().await;
-
             match expr {
                 Expr::ID(id) => {
-                    match db.remove(&id) {
-                        Some(data) => Ok(data),
+                    match items.remove(&mut items.write(&id), &id) {
+                        Some(entry) => {
+                            let _ = db_clone.events.send(Event::Delete { database: session.database, id });
+                            Ok(entry.value.to_string())
+                        },
                         None => Err(format!("Cannot delete item with an id of {:?}", id))
                     }
                 },
                 Expr::Number(mut count) => {
+                    let mut db = items.write_all();
+
                     if count == -1 {
                         count = db.len() as i32;
                     }
@@ -265,32 +719,38 @@ async fn handle_response<'a>(
                     let mut result = vec![];
                     let items: Vec<(String, String)> = db
                         .iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .take(count as usize)
-                        .map(|(id, data)| (id.clone(), data.clone()))
+                        .map(|(id, entry)| (id.clone(), entry.value.to_string()))
                         .collect();
 
                     for (id, data) in items {
                         db.remove(&id);
+                        let _ = db_clone.events.send(Event::Delete { database: session.database, id: id.clone() });
                         result.push((id, data));
                     }
 
                     Ok(format!("{:?}", result))
                 },
                 Expr::Range(start, mut end) => {
+                    let mut db = items.write_all();
+
                     if end < 0 {
-                        end = (db.len() - 1) as i32;
+                        end = db.len().saturating_sub(1) as i32;
                     }
 
                     let mut result = vec![];
                     let items: Vec<(String, String)> = db
                         .iter()
+                        .filter(|(id, _)| session.can_access(id))
                         .skip(start as usize)
                         .take((end + 1) as usize)
-                        .map(|(id, data)| (id.clone(), data.clone()))
+                        .map(|(id, entry)| (id.clone(), entry.value.to_string()))
                         .collect();
 
                     for (id, data) in items {
                         db.remove(&id);
+                        let _ = db_clone.events.send(Event::Delete { database: session.database, id: id.clone() });
                         result.push((id, data));
                     }
 
@@ -298,6 +758,746 @@ async fn handle_response<'a>(
                 },
             }
         }
+        Command::Auth { .. } => Err("AUTH must be sent on its own connection".to_string()),
+        Command::AclWhoami {  } => match &session.user {
+            Some(user) => Ok(user.name.clone()),
+            None => Err(NOAUTH.to_string()),
+        },
+        Command::ConfigGet { pattern } => {
+            let settings = db_clone.settings.read().unwrap();
+            let entries: Vec<(&str, String)> = NAMES
+                .iter()
+                .filter(|name| pattern::matches(&pattern, name))
+                .map(|name| (*name, settings.get(name).unwrap()))
+                .collect();
+
+            Ok(format!("{:?}", entries))
+        }
+        Command::ConfigSet { name, value } => {
+            db_clone.settings.write().unwrap().set(&name, &value)?;
+
+            if name == "requirepass" {
+                db_clone.auth.set_password(Some(value).filter(|password| !password.is_empty()));
+            }
+
+            Ok("OK".to_string())
+        }
+        Command::ConfigReload {  } => db_clone.reload(),
+        Command::Exists { ids } => {
+            let count = ids.iter().filter(|id| items.read(id).get(id).is_some()).count();
+
+            Ok(count.to_string())
+        }
+        Command::Rename { from, to, replace } => {
+            // Eviction can't run while the shards are locked, so make room for a longer id up front.
+            // Reading the key first keeps it from being the one evicted to make that room.
+            let grows = items.read(&from).get(&from).map_or(0, |entry| {
+                entry.touch();
+                entry.size(&to).saturating_sub(entry.size(&from))
+            });
+
+            if grows > 0 && from != to && (replace || items.read(&to).get(&to).is_none()) {
+                db_clone.make_room(session.database, &to, grows)?;
+            }
+
+            let mut db = items.write_many(&[&from, &to]);
+
+            if db.get(&from).is_none() {
+                return Err(format!("Cannot find item with an id of {from}"));
+            }
+
+            if from != to {
+                if !replace && db.get(&to).is_some() {
+                    return Ok("0".to_string());
+                }
+
+                let entry = db.remove(&from).unwrap();
+                db.insert(to.clone(), entry);
+
+                let _ = db_clone.events.send(Event::Delete { database: session.database, id: from });
+                let _ = db_clone.events.send(Event::Set { database: session.database, id: to });
+            }
+
+            match replace {
+                true => Ok("OK".to_string()),
+                false => Ok("1".to_string()),
+            }
+        }
+        Command::Copy { from, to, replace } => {
+            let not_found = || format!("Cannot find item with an id of {from}");
+
+            // Eviction can't run while the shards are locked, so make room for the copy up front.
+            let size = items.read(&from).get(&from).map(|entry| entry.size(&to)).ok_or_else(not_found)?;
+            if !replace && items.read(&to).get(&to).is_some() {
+                return Ok("0".to_string());
+            }
+
+            db_clone.make_room(session.database, &to, size)?;
+
+            let mut db = items.write_many(&[&from, &to]);
+            let value = db.get(&from).map(|entry| entry.value.clone()).ok_or_else(not_found)?;
+
+            if !replace && db.get(&to).is_some() {
+                return Ok("0".to_string());
+            }
+
+            db.insert(to.clone(), Entry::new(value));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id: to });
+
+            Ok("1".to_string())
+        }
+        Command::Type { id } => match items.read(&id).get(&id) {
+            Some(entry) => Ok(entry.value.kind().to_string()),
+            None => Ok("none".to_string()),
+        },
+        Command::Keys { expr } => {
+            let db = items.read_all();
+            let ids = db.iter().map(|(id, _)| id).filter(|id| session.can_access(id));
+
+            let result: Vec<&String> = match expr {
+                Expr::ID(pattern) => ids.filter(|id| pattern::matches(&pattern, id)).collect(),
+                Expr::Number(count) if count < 0 => ids.collect(),
+                Expr::Number(count) => ids.take(count as usize).collect(),
+                Expr::Range(start, end) if end < 0 => ids.skip(start as usize).collect(),
+                Expr::Range(start, end) => ids.skip(start as usize).take((end + 1) as usize).collect(),
+            };
+
+            Ok(format!("{:?}", result))
+        }
+        Command::Append { id, data } => {
+            // Eviction can't run while the shard is locked, so make room for the longer value up front.
+            let len = items.read(&id).get(&id).map_or(0, |entry| entry.value.len());
+            db_clone.make_room(session.database, &id, id.len() + len + data.len() + Entry::OVERHEAD)?;
+
+            let mut db = items.write_many(&[&id]);
+            let mut value = match db.get(&id) {
+                Some(entry) => entry.value.as_str()?.to_owned(),
+                None => String::new(),
+            };
+            value.push_str(&data);
+
+            let len = value.len();
+            db.insert(id.clone(), Entry::new(Value::String(value)));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(len.to_string())
+        }
+        Command::StrLen { id } => match items.read(&id).get(&id) {
+            Some(entry) => Ok(entry.value.bytes()?.len().to_string()),
+            None => Ok("0".to_string()),
+        },
+        Command::GetRange { id, start, end } => {
+            let db = items.read(&id);
+            let entry = match db.get(&id) {
+                Some(entry) => entry,
+                None => return Ok(String::new()),
+            };
+
+            entry.touch();
+
+            let bytes = entry.value.as_str()?.as_bytes();
+            match value::range(bytes.len(), start, end) {
+                Some((start, end)) => Ok(String::from_utf8_lossy(&bytes[start..=end]).into_owned()),
+                None => Ok(String::new()),
+            }
+        }
+        Command::SetRange { id, offset, data } => {
+            if offset + data.len() > MAX_VALUE_LEN {
+                return Err("SETRANGE offset is out of range".to_string());
+            }
+
+            let len = items.read(&id).get(&id).map_or(0, |entry| entry.value.len());
+            db_clone.make_room(session.database, &id, id.len() + len.max(offset + data.len()) + Entry::OVERHEAD)?;
+
+            let mut db = items.write_many(&[&id]);
+            let mut bytes = match db.get(&id) {
+                Some(entry) => entry.value.as_str()?.as_bytes().to_vec(),
+                None => vec![],
+            };
+
+            // Like redis, writing nothing to a missing key doesn't create it.
+            if data.is_empty() {
+                return Ok(bytes.len().to_string());
+            }
+
+            // Writing past the end pads the gap with zero bytes.
+            if bytes.len() < offset + data.len() {
+                bytes.resize(offset + data.len(), 0);
+            }
+            bytes[offset..offset + data.len()].copy_from_slice(data.as_bytes());
+
+            let value = String::from_utf8(bytes)
+                .map_err(|_| "SETRANGE would split a multi-byte character".to_string())?;
+
+            let len = value.len();
+            db.insert(id.clone(), Entry::new(Value::String(value)));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(len.to_string())
+        }
+        Command::SetBit { id, offset, bit } => {
+            if offset / 8 >= MAX_VALUE_LEN {
+                return Err("SETBIT offset is out of range".to_string());
+            }
+
+            let len = items.read(&id).get(&id).map_or(0, |entry| entry.value.len());
+            db_clone.make_room(session.database, &id, id.len() + len.max(offset / 8 + 1) + Entry::OVERHEAD)?;
+
+            let mut db = items.write_many(&[&id]);
+            let mut bytes = match db.get(&id) {
+                Some(entry) => entry.value.as_bitmap()?.to_vec(),
+                None => vec![],
+            };
+
+            let old = bitmap::set(&mut bytes, offset, bit);
+            db.insert(id.clone(), Entry::new(Value::Bitmap(bytes)));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(old.to_string())
+        }
+        Command::GetBit { id, offset } => match items.read(&id).get(&id) {
+            Some(entry) => {
+                entry.touch();
+                Ok(bitmap::get(entry.value.bytes()?, offset).to_string())
+            }
+            None => Ok("0".to_string()),
+        },
+        Command::BitCount { id, range } => match items.read(&id).get(&id) {
+            Some(entry) => {
+                entry.touch();
+                Ok(bitmap::count(entry.value.bytes()?, range).to_string())
+            }
+            None => Ok("0".to_string()),
+        },
+        Command::BitOp { op, destination, sources } => {
+            let len = sources
+                .iter()
+                .filter_map(|id| items.read(id).get(id).map(|entry| entry.value.len()))
+                .max()
+                .unwrap_or(0);
+            db_clone.make_room(session.database, &destination, destination.len() + len + Entry::OVERHEAD)?;
+
+            let mut ids: Vec<&str> = sources.iter().map(String::as_str).collect();
+            ids.push(&destination);
+
+            let mut db = items.write_many(&ids);
+            let bytes = {
+                let mut inputs = vec![];
+                for id in &sources {
+                    match db.get(id) {
+                        Some(entry) => inputs.push(entry.value.bytes()?),
+                        None => inputs.push(&[]),
+                    }
+                }
+
+                bitmap::op(op, &inputs)
+            };
+
+            let len = bytes.len();
+
+            // Like redis, an empty result deletes the destination instead of storing nothing.
+            match len {
+                0 => {
+                    if db.remove(&destination).is_some() {
+                        let _ = db_clone.events.send(Event::Delete { database: session.database, id: destination });
+                    }
+                }
+                _ => {
+                    db.insert(destination.clone(), Entry::new(Value::Bitmap(bytes)));
+                    let _ = db_clone.events.send(Event::Set { database: session.database, id: destination });
+                }
+            }
+
+            Ok(len.to_string())
+        }
+        Command::PfAdd { id, elements } => {
+            let size = id.len() + HyperLogLog::default().len() + Entry::OVERHEAD;
+            db_clone.make_room(session.database, &id, size)?;
+
+            let mut db = items.write_many(&[&id]);
+            let (mut hyperloglog, mut changed) = match db.get(&id) {
+                Some(entry) => (entry.value.as_hyperloglog()?.clone(), false),
+                None => (HyperLogLog::default(), true),
+            };
+
+            for element in &elements {
+                changed |= hyperloglog.add(element);
+            }
+
+            if !changed {
+                return Ok("0".to_string());
+            }
+
+            db.insert(id.clone(), Entry::new(Value::HyperLogLog(hyperloglog)));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok("1".to_string())
+        }
+        Command::PfCount { ids } => {
+            let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+            let db = items.read_many(&ids);
+
+            let mut union = HyperLogLog::default();
+            for id in ids {
+                if let Some(entry) = db.get(id) {
+                    entry.touch();
+                    union.merge(entry.value.as_hyperloglog()?);
+                }
+            }
+
+            Ok(union.count().to_string())
+        }
+        Command::PfMerge { destination, sources } => {
+            let size = destination.len() + HyperLogLog::default().len() + Entry::OVERHEAD;
+            db_clone.make_room(session.database, &destination, size)?;
+
+            let mut ids: Vec<&str> = sources.iter().map(String::as_str).collect();
+            ids.push(&destination);
+
+            // Like redis, the destination keeps what it had and gets the sources merged in.
+            let mut db = items.write_many(&ids);
+            let mut union = HyperLogLog::default();
+            for id in ids {
+                if let Some(entry) = db.get(id) {
+                    union.merge(entry.value.as_hyperloglog()?);
+                }
+            }
+
+            db.insert(destination.clone(), Entry::new(Value::HyperLogLog(union)));
+            let _ = db_clone.events.send(Event::Set { database: session.database, id: destination });
+
+            Ok("OK".to_string())
+        }
+        Command::XAdd { id, entry, fields, maxlen } => {
+            let added = fields.iter().map(|(field, value)| field.len() + value.len()).sum::<usize>() + stream::ENTRY_OVERHEAD;
+            let size = items.read(&id).get(&id).map_or(id.len() + Entry::OVERHEAD, |entry| entry.size(&id));
+            db_clone.make_room(session.database, &id, size + added)?;
+
+            let mut db = items.write_many(&[&id]);
+
+            let created = db.get(&id).is_none();
+            if created {
+                db.insert(id.clone(), Entry::new(Value::Stream(stream::Stream::default())));
+            }
+
+            let result = db
+                .update(&id, |value| {
+                    let stream = value.as_stream_mut()?;
+                    let entry = stream.add(entry, fields)?;
+
+                    if let Some(maxlen) = maxlen {
+                        stream.trim(maxlen);
+                    }
+
+                    Ok::<_, String>(entry)
+                })
+                .expect("the stream was just created");
+
+            // A rejected id doesn't leave an empty stream behind.
+            if result.is_err() && created {
+                db.remove(&id);
+            }
+
+            let entry = result?;
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(entry.to_string())
+        }
+        Command::XRange { id, start, end, count } => {
+            let db = items.read(&id);
+            let entries: Vec<(String, &Fields)> = match db.get(&id) {
+                Some(entry) => {
+                    entry.touch();
+                    entry
+                        .value
+                        .as_stream()?
+                        .range(start, end, count)
+                        .into_iter()
+                        .map(|(entry, fields)| (entry.to_string(), fields))
+                        .collect()
+                }
+                None => vec![],
+            };
+
+            Ok(format!("{:?}", entries))
+        }
+        Command::XLen { id } => match items.read(&id).get(&id) {
+            Some(entry) => Ok(entry.value.as_stream()?.len().to_string()),
+            None => Ok("0".to_string()),
+        },
+        Command::XTrim { id, maxlen } => {
+            let mut db = items.write_many(&[&id]);
+            let trimmed = match db.update(&id, |value| Ok::<_, String>(value.as_stream_mut()?.trim(maxlen))) {
+                Some(trimmed) => trimmed?,
+                None => 0,
+            };
+
+            if trimmed > 0 {
+                let _ = db_clone.events.send(Event::Set { database: session.database, id });
+            }
+
+            Ok(trimmed.to_string())
+        }
+        Command::XGroupCreate { id, group, start, mkstream } => {
+            if mkstream {
+                db_clone.make_room(session.database, &id, id.len() + Entry::OVERHEAD)?;
+            }
+
+            let mut db = items.write_many(&[&id]);
+
+            if db.get(&id).is_none() {
+                if !mkstream {
+                    return Err(format!("Cannot find stream with an id of {id}, add MKSTREAM to create it"));
+                }
+
+                db.insert(id.clone(), Entry::new(Value::Stream(stream::Stream::default())));
+                let _ = db_clone.events.send(Event::Set { database: session.database, id: id.clone() });
+            }
+
+            db.update(&id, |value| value.as_stream_mut()?.create_group(&group, start))
+                .expect("the stream exists")?;
+
+            Ok("OK".to_string())
+        }
+        Command::XReadGroup { id, group, consumer, after, count } => {
+            let mut db = items.write_many(&[&id]);
+            let entries = db
+                .update(&id, |value| value.as_stream_mut()?.read_group(&group, &consumer, after, count))
+                .ok_or_else(|| format!("NOGROUP No such key {id:?} or consumer group {group:?}"))??;
+
+            let entries: Vec<(String, Fields)> = entries
+                .into_iter()
+                .map(|(entry, fields)| (entry.to_string(), fields))
+                .collect();
+
+            Ok(format!("{:?}", entries))
+        }
+        Command::XAck { id, group, entries } => {
+            let mut db = items.write_many(&[&id]);
+            match db.update(&id, |value| value.as_stream_mut()?.ack(&group, &entries)) {
+                Some(acked) => Ok(acked?.to_string()),
+                None => Ok("0".to_string()),
+            }
+        }
+        Command::XPending { id, group } => {
+            let db = items.read(&id);
+            let stream = match db.get(&id) {
+                Some(entry) => entry.value.as_stream()?,
+                None => return Err(format!("NOGROUP No such key {id:?} or consumer group {group:?}")),
+            };
+
+            let now = stream::now();
+            let pending: Vec<(String, &String, u64, u32)> = stream
+                .pending(&group)?
+                .into_iter()
+                .map(|(entry, pending)| {
+                    (entry.to_string(), &pending.consumer, now.saturating_sub(pending.delivered_at), pending.deliveries)
+                })
+                .collect();
+
+            Ok(format!("{:?}", pending))
+        }
+        Command::GeoAdd { id, members } => {
+            let added: usize = members.iter().map(|(_, member)| member.len() + geo::MEMBER_OVERHEAD).sum();
+            let size = items.read(&id).get(&id).map_or(id.len() + Entry::OVERHEAD, |entry| entry.size(&id));
+            db_clone.make_room(session.database, &id, size + added)?;
+
+            let mut db = items.write_many(&[&id]);
+            if db.get(&id).is_none() {
+                db.insert(id.clone(), Entry::new(Value::Geo(Geo::default())));
+            }
+
+            let results = db
+                .update(&id, |value| {
+                    let geo = value.as_geo_mut()?;
+                    Ok::<_, String>(members.iter().map(|(point, member)| geo.add(member, *point)).collect::<Vec<Added>>())
+                })
+                .expect("the geo value was just created")?;
+
+            if results.iter().any(|result| *result != Added::Unchanged) {
+                let _ = db_clone.events.send(Event::Set { database: session.database, id });
+            }
+
+            Ok(results.iter().filter(|result| **result == Added::New).count().to_string())
+        }
+        Command::GeoPos { id, members } => {
+            let db = items.read(&id);
+            let geo = match db.get(&id) {
+                Some(entry) => Some(entry.value.as_geo()?),
+                None => None,
+            };
+
+            // Each position is "<lon> <lat>", or "nil" for members that aren't there.
+            let positions: Vec<String> = members
+                .iter()
+                .map(|member| match geo.and_then(|geo| geo.position(member)) {
+                    Some(point) => format!("{:.6} {:.6}", point.lon, point.lat),
+                    None => "nil".to_string(),
+                })
+                .collect();
+
+            Ok(format!("{:?}", positions))
+        }
+        Command::GeoDist { id, from, to, unit } => {
+            let db = items.read(&id);
+            let geo = match db.get(&id) {
+                Some(entry) => entry.value.as_geo()?,
+                None => return Err(format!("Cannot find item with an id of {id}")),
+            };
+
+            let position = |member: &str| geo.position(member).ok_or(format!("Cannot find member {member:?}"));
+            let distance = position(&from)?.distance(&position(&to)?);
+
+            Ok(format!("{:.4}", distance / unit.meters()))
+        }
+        Command::GeoSearch { id, center, shape, unit, options } => {
+            let db = items.read(&id);
+            let geo = match db.get(&id) {
+                Some(entry) => entry.value.as_geo()?,
+                None => return Ok("[]".to_string()),
+            };
+
+            let center = match center {
+                Center::Point(point) => point,
+                Center::Member(member) => geo.position(&member).ok_or(format!("Cannot find member {member:?}"))?,
+            };
+
+            let mut found = geo.search(center, shape);
+            if options.descending {
+                found.reverse();
+            }
+            found.truncate(options.count.unwrap_or(usize::MAX));
+
+            if !options.with_dist && !options.with_coord {
+                let members: Vec<&String> = found.into_iter().map(|(member, _)| member).collect();
+                return Ok(format!("{:?}", members));
+            }
+
+            let found: Vec<Vec<String>> = found
+                .into_iter()
+                .map(|(member, distance)| {
+                    let mut fields = vec![member.to_owned()];
+
+                    if options.with_dist {
+                        fields.push(format!("{:.4}", distance / unit.meters()));
+                    }
+
+                    if let Some(point) = geo.position(member).filter(|_| options.with_coord) {
+                        fields.push(format!("{:.6}", point.lon));
+                        fields.push(format!("{:.6}", point.lat));
+                    }
+
+                    fields
+                })
+                .collect();
+
+            Ok(format!("{:?}", found))
+        }
+        Command::JsonSet { id, path, value } => {
+            let size = items.read(&id).get(&id).map_or(id.len() + Entry::OVERHEAD, |entry| entry.size(&id));
+            db_clone.make_room(session.database, &id, size + value.to_string().len())?;
+
+            let mut db = items.write_many(&[&id]);
+
+            match (db.get(&id).is_some(), path.is_root()) {
+                // The root replaces whatever was there, like `SET` does.
+                (_, true) => db.insert(id.clone(), Entry::new(Value::Json(Document::new(value)))),
+                (true, false) => db
+                    .update(&id, |existing| existing.as_json_mut()?.set(&path, value))
+                    .expect("the key exists")?,
+                (false, false) => return Err(format!("Cannot find item with an id of {id}, set $ first")),
+            }
+
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok("OK".to_string())
+        }
+        Command::JsonGet { id, path } => {
+            let db = items.read(&id);
+            let entry = match db.get(&id) {
+                Some(entry) => entry,
+                None => return Err(format!("Cannot find item with an id of {id}")),
+            };
+
+            entry.touch();
+            Ok(entry.value.as_json()?.get(&path)?.to_string())
+        }
+        Command::JsonDelete { id, path } => {
+            let mut db = items.write_many(&[&id]);
+
+            let deleted = match path.is_root() {
+                true => match db.get(&id) {
+                    Some(entry) => {
+                        entry.value.as_json()?;
+                        db.remove(&id).is_some()
+                    }
+                    None => false,
+                },
+                false => match db.update(&id, |value| Ok::<_, String>(value.as_json_mut()?.delete(&path))) {
+                    Some(deleted) => deleted?,
+                    None => false,
+                },
+            };
+
+            if deleted {
+                let event = match path.is_root() {
+                    true => Event::Delete { database: session.database, id },
+                    false => Event::Set { database: session.database, id },
+                };
+
+                let _ = db_clone.events.send(event);
+            }
+
+            Ok((deleted as u8).to_string())
+        }
+        Command::JsonArrAppend { id, path, values } => {
+            let added: usize = values.iter().map(|value| value.to_string().len() + 1).sum();
+            let size = items.read(&id).get(&id).map_or(id.len() + Entry::OVERHEAD, |entry| entry.size(&id));
+            db_clone.make_room(session.database, &id, size + added)?;
+
+            let mut db = items.write_many(&[&id]);
+            let len = db
+                .update(&id, |value| value.as_json_mut()?.append(&path, values))
+                .ok_or_else(|| format!("Cannot find item with an id of {id}"))??;
+
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(len.to_string())
+        }
+        Command::JsonNumIncrBy { id, path, by } => {
+            let mut db = items.write_many(&[&id]);
+            let number = db
+                .update(&id, |value| value.as_json_mut()?.increment(&path, &by))
+                .ok_or_else(|| format!("Cannot find item with an id of {id}"))??;
+
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok(number.to_string())
+        }
+        Command::IndexCreate { name, pattern, field } => {
+            items.create_index(&name, &pattern, field)?;
+            Ok("OK".to_string())
+        }
+        Command::IndexDrop { name } => {
+            Ok((items.secondary().write().delete(&name) as u8).to_string())
+        }
+        Command::IndexList {  } => {
+            Ok(format!("{:?}", items.secondary().read().list()))
+        }
+        Command::Find { name, query } => {
+            let indexes = items.secondary().read();
+            let ids: Vec<String> = indexes.find(&name, &query)?.into_iter().filter(|id| session.can_access(id)).collect();
+
+            Ok(format!("{:?}", ids))
+        }
+        Command::Search { name, query } => {
+            let indexes = items.secondary().read();
+            let ids: Vec<String> = indexes
+                .search(&name, &query)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .filter(|id| session.can_access(id))
+                .collect();
+
+            Ok(format!("{:?}", ids))
+        }
+        Command::VAdd { id, vector } => {
+            db_clone.insert(session.database, id.clone(), Entry::new(Value::Vector(vector)))?;
+            let _ = db_clone.events.send(Event::Set { database: session.database, id });
+
+            Ok("OK".to_string())
+        }
+        Command::VGet { id } => {
+            let db = items.read(&id);
+            let entry = match db.get(&id) {
+                Some(entry) => entry,
+                None => return Err(format!("Cannot find item with an id of {id}")),
+            };
+
+            entry.touch();
+            Ok(entry.value.as_vector()?.to_string())
+        }
+        Command::VSearch { name, k, vector, exact, with_dist } => {
+            let indexes = items.secondary().read();
+            let found = indexes.vsearch(&name, vector, k, exact, &|id| session.can_access(id))?.into_iter();
+
+            match with_dist {
+                true => {
+                    let found: Vec<Vec<String>> = found.map(|(id, distance)| vec![id, format!("{distance:.4}")]).collect();
+                    Ok(format!("{:?}", found))
+                }
+                false => Ok(format!("{:?}", found.map(|(id, _)| id).collect::<Vec<String>>())),
+            }
+        }
+        Command::Eval { script, keys, args } => {
+            let (_, ast) = db_clone.scripts.load(&script)?;
+            script::run(db_clone, session, &ast, keys, args)
+        }
+        Command::EvalSha { sha, keys, args } => match db_clone.scripts.get(&sha) {
+            Some(ast) => script::run(db_clone, session, &ast, keys, args),
+            None => Err(NOSCRIPT.to_string()),
+        },
+        Command::ScriptLoad { script } => Ok(db_clone.scripts.load(&script)?.0),
+        Command::NamespaceList {  } => {
+            let db = items.read_all();
+
+            // Users limited to some keys only see those counted, everyone else gets the index sizes as they are.
+            let visible = |id: &str| session.can_access(id);
+            let namespaces = match session.user.as_ref().is_some_and(|user| user.can_access_everything()) {
+                true => db.namespaces(None),
+                false => db.namespaces(Some(&visible)),
+            };
+
+            Ok(format!("{:?}", namespaces.into_iter().collect::<Vec<(String, usize)>>()))
+        }
+        Command::NamespaceDrop { namespace } => {
+            let mut db = items.write_all();
+            let ids: Vec<String> = db
+                .namespace(&namespace)
+                .into_iter()
+                .filter(|(id, _)| session.can_access(id))
+                .map(|(id, _)| id.to_owned())
+                .collect();
+
+            for id in &ids {
+                db.remove(id);
+                let _ = db_clone.events.send(Event::Delete { database: session.database, id: id.to_owned() });
+            }
+
+            Ok(ids.len().to_string())
+        }
+        Command::FlushDb {  } => {
+            flush(db_clone, session, session.database);
+            Ok("OK".to_string())
+        }
+        Command::FlushAll {  } => {
+            for database in 0..db_clone.databases.len() {
+                flush(db_clone, session, database);
+            }
+
+            Ok("OK".to_string())
+        }
+        Command::Select { .. } => Err("SELECT is handled per connection".to_string()),
+        Command::Shutdown { save } => {
+            db_clone.shutdown(if save { "SHUTDOWN" } else { "SHUTDOWN NOSAVE" });
+            Ok("OK".to_string())
+        }
+        Command::AclList {  } => {
+            let users: Vec<String> = db_clone.auth.users().iter().map(|user| user.describe()).collect();
+
+            Ok(format!("{:?}", users))
+        }
+        Command::Subscribe { .. } | Command::Unsubscribe { .. } => {
+            Err("Subscriptions are only available over websocket".to_string())
+        }
         Command::Invalid { reason } => Err(reason.to_string()),
     }
 }
+
+pub fn format_response(format: &str, status: &str, response: &str) -> String {
+    match format {
+        "default" => format!("{status} {response}"),
+        "json" => json!({ "status": status, "response": response }).to_string(),
+        _ => unreachable!()
+    }
+}
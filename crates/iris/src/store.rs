@@ -0,0 +1,327 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::BuildHasher,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use indexmap::IndexMap;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+    index::{Field, Indexes},
+    memory::{self, Entry, Policy},
+    namespace::Namespaces,
+    value::Value,
+};
+
+/// One slice of the keyspace and the namespace index of its keys.
+#[derive(Default)]
+pub struct Shard {
+    /// Indexed by position too, so eviction can sample keys without walking the whole map.
+    items: IndexMap<String, Entry>,
+    namespaces: Namespaces,
+}
+
+impl Shard {
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.items.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Entry)> {
+        self.items.iter()
+    }
+}
+
+/// How many keys of each shard eviction compares, like `maxmemory-samples` in Redis.
+const SAMPLES: usize = 5;
+
+/// How many keys one eviction pass collects from a database before it stops visiting shards.
+const POOL: usize = 16;
+
+/// How many shards the keys are spread over. More than there are cores, so two busy connections rarely share one.
+const SHARDS: usize = 64;
+
+/// The keys, split over independently locked shards by the hash of their id.
+///
+/// Commands on one key only lock its shard, reads share it. The locks don't poison, so a command
+/// that panics halfway only loses its own connection instead of every later command on the shard. Commands spanning shards lock them through
+/// [`Shards::read_all`], [`Shards::write_all`] or [`Shards::write_many`], which always go in ascending
+/// shard order so two of them can never wait on each other. [`Shards::read_many`] does the same for reads.
+pub struct Shards {
+    shards: Vec<RwLock<Shard>>,
+    hasher: RandomState,
+    /// Splits ids into namespaces, see [`Namespaces`].
+    delimiter: String,
+    /// The approximate bytes the keys of every database take up together, see [`Entry::size`].
+    used: Arc<AtomicUsize>,
+    /// The indexes of `INDEX CREATE`. Always locked after the shards, never before, and only locked for
+    /// writing to create or drop an index. Writes to keys share it and lock just the indexes covering them.
+    secondary: RwLock<Indexes>,
+}
+
+impl Shards {
+    pub fn new(used: Arc<AtomicUsize>, delimiter: &str) -> Shards {
+        Shards {
+            shards: (0..SHARDS).map(|_| RwLock::new(Shard::default())).collect(),
+            hasher: RandomState::new(),
+            delimiter: delimiter.to_string(),
+            used,
+            secondary: RwLock::new(Indexes::default()),
+        }
+    }
+
+    pub fn index(&self, id: &str) -> usize {
+        self.hasher.hash_one(id) as usize % self.shards.len()
+    }
+
+    pub fn read(&self, id: &str) -> RwLockReadGuard<'_, Shard> {
+        self.shards[self.index(id)].read()
+    }
+
+    pub fn write(&self, id: &str) -> RwLockWriteGuard<'_, Shard> {
+        self.shards[self.index(id)].write()
+    }
+
+    /// Every shard, locked for reading in ascending order.
+    pub fn read_all(&self) -> Locked<'_, RwLockReadGuard<'_, Shard>> {
+        Locked {
+            shards: self,
+            guards: self.shards.iter().map(|shard| shard.read()).enumerate().collect(),
+        }
+    }
+
+    /// Every shard, locked for writing in ascending order.
+    pub fn write_all(&self) -> Locked<'_, RwLockWriteGuard<'_, Shard>> {
+        Locked {
+            shards: self,
+            guards: self.shards.iter().map(|shard| shard.write()).enumerate().collect(),
+        }
+    }
+
+    /// The shards of `ids`, each locked once for reading in ascending order.
+    pub fn read_many(&self, ids: &[&str]) -> Locked<'_, RwLockReadGuard<'_, Shard>> {
+        Locked {
+            shards: self,
+            guards: self
+                .indexes(ids)
+                .into_iter()
+                .map(|index| (index, self.shards[index].read()))
+                .collect(),
+        }
+    }
+
+    /// The shards of `ids`, each locked once for writing in ascending order.
+    pub fn write_many(&self, ids: &[&str]) -> Locked<'_, RwLockWriteGuard<'_, Shard>> {
+        Locked {
+            shards: self,
+            guards: self
+                .indexes(ids)
+                .into_iter()
+                .map(|index| (index, self.shards[index].write()))
+                .collect(),
+        }
+    }
+
+    fn indexes(&self, ids: &[&str]) -> Vec<usize> {
+        let mut indexes: Vec<usize> = ids.iter().map(|id| self.index(id)).collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        indexes
+    }
+
+    /// Inserts without checking `maxmemory`, that's up to the caller.
+    pub fn insert(&self, id: String, entry: Entry) {
+        self.insert_into(&mut self.write(&id), id, entry);
+    }
+
+    /// Inserts into an already locked shard without checking `maxmemory`.
+    fn insert_into(&self, shard: &mut Shard, id: String, entry: Entry) {
+        self.used.fetch_add(entry.size(&id), Ordering::Relaxed);
+        self.reindex(&id, Some(&entry.value));
+
+        match shard.items.insert(id.clone(), entry) {
+            Some(old) => {
+                self.used.fetch_sub(old.size(&id), Ordering::Relaxed);
+            }
+            None => shard.namespaces.add(&id, &self.delimiter),
+        }
+    }
+
+    /// Removes from an already locked shard.
+    pub fn remove(&self, shard: &mut Shard, id: &str) -> Option<Entry> {
+        let entry = shard.items.swap_remove(id)?;
+        self.used.fetch_sub(entry.size(id), Ordering::Relaxed);
+        shard.namespaces.remove(id, &self.delimiter);
+        self.reindex(id, None);
+
+        Some(entry)
+    }
+
+    /// Indexes the keys matching `pattern` by `field`, including the ones already there.
+    pub fn create_index(&self, name: &str, pattern: &str, field: Field) -> Result<(), String> {
+        // Holding every shard keeps writes out until the index is registered, so none are missed.
+        let items = self.read_all();
+        let result = self.secondary.write().create(name, pattern, field, items.iter());
+
+        result
+    }
+
+    /// The indexes of `INDEX CREATE`, for `FIND`, `SEARCH`, `INDEX LIST` and `INDEX DROP`.
+    pub fn secondary(&self) -> &RwLock<Indexes> {
+        &self.secondary
+    }
+
+    /// Keeps the indexes in step with a key that was set, or removed when `value` is `None`.
+    /// Runs with the shard of the key locked, so the key can't change again in between.
+    fn reindex(&self, id: &str, value: Option<&Value>) {
+        self.secondary.read().set(id, value);
+    }
+
+    /// Up to about [`POOL`] keys `policy` may evict, with their rank. Takes [`SAMPLES`] random keys of each shard,
+    /// or all of a shard with fewer, starting at a random shard and stopping once the pool is full.
+    /// Leaves out `skip`, the key being written.
+    pub fn candidates(&self, policy: Policy, skip: Option<&str>) -> Vec<((u64, u64), String)> {
+        let start = memory::random() as usize % self.shards.len();
+        let mut candidates = vec![];
+
+        for index in (start..self.shards.len()).chain(0..start) {
+            if candidates.len() >= POOL {
+                break;
+            }
+
+            let shard = self.shards[index].read();
+            let sample: Vec<usize> = match shard.len() {
+                len if len <= SAMPLES => (0..len).collect(),
+                len => (0..SAMPLES).map(|_| memory::random() as usize % len).collect(),
+            };
+
+            candidates.extend(
+                sample
+                    .into_iter()
+                    .filter_map(|index| shard.items.get_index(index))
+                    .filter(|(id, _)| Some(id.as_str()) != skip)
+                    .filter_map(|(id, entry)| Some((policy.rank(entry)?, id.to_owned()))),
+            );
+        }
+
+        candidates
+    }
+}
+
+/// Several shards locked together, used like one map.
+pub struct Locked<'a, G> {
+    shards: &'a Shards,
+    guards: Vec<(usize, G)>,
+}
+
+impl<G: Deref<Target = Shard>> Locked<'_, G> {
+    /// Looks up a key. Panics if its shard isn't locked.
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        let index = self.shards.index(id);
+
+        self.guards
+            .iter()
+            .find(|(locked, _)| *locked == index)
+            .expect("the shard of the id isn't locked")
+            .1
+            .get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.guards.iter().map(|(_, shard)| shard.len()).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Entry)> {
+        self.guards.iter().flat_map(|(_, shard)| shard.iter())
+    }
+
+    /// Every namespace with how many keys it holds, sorted by name. Only counts the keys `visible` allows,
+    /// or takes the size of the index without looking at every key when it's `None`.
+    pub fn namespaces(&self, visible: Option<&dyn Fn(&str) -> bool>) -> BTreeMap<String, usize> {
+        let mut namespaces: BTreeMap<String, usize> = BTreeMap::new();
+
+        for (_, shard) in &self.guards {
+            for (namespace, keys) in shard.namespaces.iter() {
+                let count = match visible {
+                    Some(visible) => keys.iter().filter(|id| visible(id)).count(),
+                    None => keys.len(),
+                };
+
+                if count > 0 {
+                    *namespaces.entry(namespace.to_owned()).or_default() += count;
+                }
+            }
+        }
+
+        namespaces
+    }
+
+    /// The keys of a namespace, or every key without one.
+    pub fn entries(&self, namespace: Option<&str>) -> Vec<(&String, &Entry)> {
+        match namespace {
+            Some(namespace) => self.namespace(namespace),
+            None => self.iter().collect(),
+        }
+    }
+
+    /// The keys of a namespace, sorted by id.
+    pub fn namespace(&self, namespace: &str) -> Vec<(&String, &Entry)> {
+        let mut entries: Vec<(&String, &Entry)> = self
+            .guards
+            .iter()
+            .flat_map(|(_, shard)| shard.namespaces.keys(namespace).map(|id| (id, &shard.items[id])))
+            .collect();
+
+        entries.sort_by_key(|(id, _)| *id);
+        entries
+    }
+}
+
+impl<G: DerefMut<Target = Shard>> Locked<'_, G> {
+    fn shard(&mut self, id: &str) -> &mut Shard {
+        let index = self.shards.index(id);
+
+        self.guards
+            .iter_mut()
+            .find(|(locked, _)| *locked == index)
+            .map(|(_, shard)| &mut **shard)
+            .expect("the shard of the id isn't locked")
+    }
+
+    /// Inserts without checking `maxmemory`, see [`Shards::insert`].
+    pub fn insert(&mut self, id: String, entry: Entry) {
+        let shards = self.shards;
+        shards.insert_into(self.shard(&id), id, entry);
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Entry> {
+        let shards = self.shards;
+        shards.remove(self.shard(id), id)
+    }
+
+    /// Changes a value in place, for the types too big to copy on every write.
+    /// `None` when the key doesn't exist. Doesn't check `maxmemory`, see [`Shards::insert`].
+    pub fn update<T>(&mut self, id: &str, change: impl FnOnce(&mut Value) -> T) -> Option<T> {
+        let shards = self.shards;
+        let entry = self.shard(id).items.get_mut(id)?;
+
+        let before = entry.size(id);
+        let result = change(&mut entry.value);
+        let after = entry.size(id);
+
+        shards.used.fetch_add(after, Ordering::Relaxed);
+        shards.used.fetch_sub(before, Ordering::Relaxed);
+        shards.reindex(id, Some(&entry.value));
+
+        Some(result)
+    }
+}
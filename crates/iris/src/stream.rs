@@ -0,0 +1,240 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::Bound,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// What a stream entry costs besides its fields, the id and the map slot.
+pub const ENTRY_OVERHEAD: usize = 48;
+
+/// `<milliseconds>-<sequence>`, ordered by time first and by the order of adds in the same millisecond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId { ms: u64::MAX, seq: u64::MAX };
+
+    /// Parses a bound of `XRANGE`, where `-` and `+` are the first and last id. A bare `<ms>` starts at the
+    /// first sequence of the millisecond, or ends at its last when `end` is set.
+    pub fn parse_bound(bound: &str, end: bool) -> Result<StreamId, String> {
+        match bound {
+            "-" => Ok(StreamId::MIN),
+            "+" => Ok(StreamId::MAX),
+            _ if !bound.contains('-') => match bound.parse::<u64>() {
+                Ok(ms) => Ok(StreamId { ms, seq: if end { u64::MAX } else { 0 } }),
+                Err(_) => Err(format!("Invalid stream id {bound:?}")),
+            },
+            _ => bound.parse(),
+        }
+    }
+}
+
+impl FromStr for StreamId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<StreamId, String> {
+        let parse = |part: &str| part.parse::<u64>().map_err(|_| format!("Invalid stream id {id:?}"));
+
+        match id.split_once('-') {
+            Some((ms, seq)) => Ok(StreamId { ms: parse(ms)?, seq: parse(seq)? }),
+            None => Ok(StreamId { ms: parse(id)?, seq: 0 }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// An entry delivered to a consumer and not acknowledged yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pending {
+    pub consumer: String,
+    /// When it was last delivered, in milliseconds since the epoch.
+    pub delivered_at: u64,
+    pub deliveries: u32,
+}
+
+/// Consumers reading a stream together, each new entry going to one of them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Group {
+    /// The last id handed out with `>`.
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, Pending>,
+}
+
+pub type Fields = Vec<(String, String)>;
+
+/// An append-only log of field-value entries with increasing ids.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    /// The highest id ever added, which trimming doesn't lower, so ids never repeat.
+    last_id: StreamId,
+    groups: BTreeMap<String, Group>,
+    /// The approximate bytes of the entries' fields, added to on `XADD` and taken from on `XTRIM`.
+    size: usize,
+}
+
+impl Stream {
+    /// Adds an entry under `id`, or the next id from the clock when `None`. Returns the id it got.
+    pub fn add(&mut self, id: Option<StreamId>, fields: Fields) -> Result<StreamId, String> {
+        let id = match id {
+            Some(id) if id > self.last_id => id,
+            Some(_) => return Err("The ID specified in XADD is equal or smaller than the target stream top item".to_string()),
+            None => {
+                let ms = now();
+
+                // A clock that went backwards keeps counting on the last millisecond instead,
+                // moving on to the next one once its sequence runs out.
+                match (ms > self.last_id.ms, self.last_id.seq.checked_add(1), self.last_id.ms.checked_add(1)) {
+                    (true, ..) => StreamId { ms, seq: 0 },
+                    (false, Some(seq), _) => StreamId { ms: self.last_id.ms, seq },
+                    (false, None, Some(ms)) => StreamId { ms, seq: 0 },
+                    (false, None, None) => return Err("The stream has exhausted the last possible ID".to_string()),
+                }
+            }
+        };
+
+        self.size += entry_size(&fields);
+        self.entries.insert(id, fields);
+        self.last_id = id;
+
+        Ok(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The entries from `start` to `end` inclusive, at most `count` of them.
+    pub fn range(&self, start: StreamId, end: StreamId, count: Option<usize>) -> Vec<(StreamId, &Fields)> {
+        if start > end {
+            return vec![];
+        }
+
+        self.entries
+            .range(start..=end)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields))
+            .collect()
+    }
+
+    /// Drops the oldest entries until at most `max` are left. Returns how many were dropped.
+    pub fn trim(&mut self, max: usize) -> usize {
+        let mut trimmed = 0;
+
+        while self.entries.len() > max {
+            if let Some((_, fields)) = self.entries.pop_first() {
+                self.size -= entry_size(&fields);
+                trimmed += 1;
+            }
+        }
+
+        trimmed
+    }
+
+    /// Creates a group that delivers the entries after `start`, or only new ones when `None`.
+    pub fn create_group(&mut self, name: &str, start: Option<StreamId>) -> Result<(), String> {
+        if self.groups.contains_key(name) {
+            return Err("BUSYGROUP Consumer Group name already exists".to_string());
+        }
+
+        let group = Group { last_delivered: start.unwrap_or(self.last_id), ..Default::default() };
+        self.groups.insert(name.to_string(), group);
+
+        Ok(())
+    }
+
+    /// With `after` as `None` hands `consumer` entries no one in the group got yet, marking them pending.
+    /// Otherwise delivers its own pending entries after that id again, for picking up after a crash.
+    ///
+    /// Pending entries that were trimmed away come back without fields.
+    pub fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: Option<usize>,
+    ) -> Result<Vec<(StreamId, Fields)>, String> {
+        let group = self.groups.get_mut(group).ok_or_else(|| no_group(group))?;
+        let count = count.unwrap_or(usize::MAX);
+        let delivered_at = now();
+
+        let ids: Vec<StreamId> = match after {
+            None => self
+                .entries
+                .range((Bound::Excluded(group.last_delivered), Bound::Unbounded))
+                .take(count)
+                .map(|(id, _)| *id)
+                .collect(),
+            Some(after) => group
+                .pending
+                .range((Bound::Excluded(after), Bound::Unbounded))
+                .filter(|(_, pending)| pending.consumer == consumer)
+                .take(count)
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+
+        for id in &ids {
+            let pending = group.pending.entry(*id).or_insert(Pending {
+                consumer: consumer.to_string(),
+                delivered_at,
+                deliveries: 0,
+            });
+
+            pending.delivered_at = delivered_at;
+            pending.deliveries += 1;
+
+            if after.is_none() {
+                group.last_delivered = *id;
+            }
+        }
+
+        Ok(ids
+            .into_iter()
+            .map(|id| (id, self.entries.get(&id).cloned().unwrap_or_default()))
+            .collect())
+    }
+
+    /// Marks entries as processed so they're no longer pending. Returns how many were.
+    pub fn ack(&mut self, group: &str, ids: &[StreamId]) -> Result<usize, String> {
+        let group = self.groups.get_mut(group).ok_or_else(|| no_group(group))?;
+
+        Ok(ids.iter().filter(|id| group.pending.remove(id).is_some()).count())
+    }
+
+    /// The entries of a group that were delivered and not acknowledged yet, oldest first.
+    pub fn pending(&self, group: &str) -> Result<Vec<(StreamId, &Pending)>, String> {
+        let group = self.groups.get(group).ok_or_else(|| no_group(group))?;
+
+        Ok(group.pending.iter().map(|(id, pending)| (*id, pending)).collect())
+    }
+
+    /// The approximate bytes the entries take up.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+fn entry_size(fields: &Fields) -> usize {
+    fields.iter().map(|(field, value)| field.len() + value.len()).sum::<usize>() + ENTRY_OVERHEAD
+}
+
+fn no_group(group: &str) -> String {
+    format!("NOGROUP No such consumer group {group:?}")
+}
+
+/// Milliseconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis() as u64)
+}
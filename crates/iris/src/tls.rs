@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor, TlsConnector,
+};
+
+/// Any connection the line protocol can run over, plain or encrypted.
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Builds the server side of TLS. With `client_ca`, clients must present a certificate signed by it (mTLS).
+pub fn acceptor(cert: &str, key: &str, client_ca: Option<&str>) -> Result<TlsAcceptor, String> {
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let verifier = WebPkiClientVerifier::builder(Arc::new(load_roots(client_ca)?))
+                .build()
+                .map_err(|err| format!("Invalid client ca certificate: {err}"))?;
+
+            ServerConfig::builder().with_client_cert_verifier(verifier)
+        }
+        None => ServerConfig::builder().with_no_client_auth(),
+    };
+
+    let config = builder
+        .with_single_cert(load_certs(cert)?, load_key(key)?)
+        .map_err(|err| format!("Invalid tls certificate or key: {err}"))?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Builds the client side of TLS, trusting `ca` (or the webpki roots without one) and
+/// optionally presenting a client certificate.
+pub fn connector(ca: Option<&str>, identity: Option<(&str, &str)>) -> Result<TlsConnector, String> {
+    let roots = match ca {
+        Some(ca) => load_roots(ca)?,
+        None => RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() },
+    };
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+
+    let config = match identity {
+        Some((cert, key)) => builder
+            .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(|err| format!("Invalid client certificate or key: {err}"))?,
+        None => builder.with_no_client_auth(),
+    };
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| format!("Failed to read certificates from {path}: {err}"))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {path}"));
+    }
+
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    PrivateKeyDer::from_pem_file(path).map_err(|err| format!("Failed to read the private key from {path}: {err}"))
+}
+
+fn load_roots(path: &str) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+
+    for cert in load_certs(path)? {
+        roots
+            .add(cert)
+            .map_err(|err| format!("Invalid ca certificate in {path}: {err}"))?;
+    }
+
+    Ok(roots)
+}
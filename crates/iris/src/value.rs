@@ -0,0 +1,163 @@
+use std::fmt;
+
+use crate::{geo::Geo, hyperloglog::HyperLogLog, json::Document, stream::Stream, vector::Vector};
+
+pub const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+/// What a key holds. `SET` stores strings, the other types come from their own commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    /// Raw bytes written by `SETBIT` and `BITOP`, which don't have to be valid utf-8.
+    Bitmap(Vec<u8>),
+    /// Distinct element estimates from `PFADD` and `PFMERGE`.
+    HyperLogLog(HyperLogLog),
+    /// An append-only log from `XADD`.
+    Stream(Stream),
+    /// Named positions from `GEOADD`.
+    Geo(Geo),
+    /// A JSON document from `JSET`.
+    Json(Document),
+    /// An embedding from `VADD`.
+    Vector(Vector),
+}
+
+impl Value {
+    /// The name `TYPE` answers with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Bitmap(_) => "bitmap",
+            Value::HyperLogLog(_) => "hyperloglog",
+            Value::Stream(_) => "stream",
+            Value::Geo(_) => "geo",
+            Value::Json(_) => "json",
+            Value::Vector(_) => "vector",
+        }
+    }
+
+    /// The approximate bytes the value takes up, see [`crate::memory::Entry::size`].
+    pub fn len(&self) -> usize {
+        match self {
+            Value::String(data) => data.len(),
+            Value::Bitmap(bytes) => bytes.len(),
+            Value::HyperLogLog(hyperloglog) => hyperloglog.len(),
+            Value::Stream(stream) => stream.size(),
+            Value::Geo(geo) => geo.size(),
+            Value::Json(document) => document.size(),
+            Value::Vector(vector) => vector.len() * size_of::<f32>(),
+        }
+    }
+
+    /// The value as text, for the commands that only work on strings.
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::String(data) => Ok(data),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    /// The raw bytes, which the bit commands read strings as too.
+    pub fn bytes(&self) -> Result<&[u8], String> {
+        match self {
+            Value::String(data) => Ok(data.as_bytes()),
+            Value::Bitmap(bytes) => Ok(bytes),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_bitmap(&self) -> Result<&[u8], String> {
+        match self {
+            Value::Bitmap(bytes) => Ok(bytes),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_hyperloglog(&self) -> Result<&HyperLogLog, String> {
+        match self {
+            Value::HyperLogLog(hyperloglog) => Ok(hyperloglog),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_stream(&self) -> Result<&Stream, String> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_stream_mut(&mut self) -> Result<&mut Stream, String> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_geo(&self) -> Result<&Geo, String> {
+        match self {
+            Value::Geo(geo) => Ok(geo),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_geo_mut(&mut self) -> Result<&mut Geo, String> {
+        match self {
+            Value::Geo(geo) => Ok(geo),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_json(&self) -> Result<&Document, String> {
+        match self {
+            Value::Json(document) => Ok(document),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_json_mut(&mut self) -> Result<&mut Document, String> {
+        match self {
+            Value::Json(document) => Ok(document),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    pub fn as_vector(&self) -> Result<&Vector, String> {
+        match self {
+            Value::Vector(vector) => Ok(vector),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+}
+
+/// How `LST` and `DEL` show a value. Bitmaps aren't text, so they're shown as hex, a HyperLogLog as the
+/// count it estimates, streams and geo values as how many entries or members they have, JSON as compact JSON and vectors as a list.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(data) => write!(f, "{data}"),
+            Value::Bitmap(bytes) => bytes.iter().try_for_each(|byte| write!(f, "{byte:02x}")),
+            Value::HyperLogLog(hyperloglog) => write!(f, "{}", hyperloglog.count()),
+            Value::Stream(stream) => write!(f, "{}", stream.len()),
+            Value::Geo(geo) => write!(f, "{}", geo.len()),
+            Value::Json(document) => write!(f, "{}", document.root()),
+            Value::Vector(vector) => write!(f, "{vector}"),
+        }
+    }
+}
+
+/// Resolves an inclusive range with negative offsets counting from the end, like `GETRANGE` and `BITCOUNT` take it.
+/// `None` when nothing of a `len` long value is in it.
+pub fn range(len: usize, start: i32, end: i32) -> Option<(usize, usize)> {
+    let resolve = |offset: i32| match offset < 0 {
+        true => (len as i64 + offset as i64).max(0),
+        false => offset as i64,
+    };
+
+    let (start, end) = (resolve(start), resolve(end).min(len as i64 - 1));
+
+    match start <= end {
+        true => Some((start as usize, end as usize)),
+        false => None,
+    }
+}
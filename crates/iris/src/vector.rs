@@ -0,0 +1,387 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use crate::{json, value::Value};
+
+/// Neighbours per node above the bottom layer of the graph, the bottom layer keeps twice as many.
+const M: usize = 16;
+
+/// How many candidates an insert looks at to pick neighbours from.
+const EF_CONSTRUCTION: usize = 100;
+
+/// How many candidates a search looks at at least, more than `k` so the graph finds the true nearest.
+const EF_SEARCH: usize = 64;
+
+/// A vector of `f32`s from `VADD`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vector(pub Vec<f32>);
+
+/// [`Vector::parse`] refuses `NaN`, so every vector equals itself.
+impl Eq for Vector {}
+
+impl Vector {
+    /// Parses the components of `VADD` and `VSEARCH`.
+    pub fn parse(components: &[&str]) -> Result<Vector, String> {
+        components
+            .iter()
+            .map(|component| match component.parse::<f32>() {
+                Ok(component) if component.is_finite() => Ok(component),
+                _ => Err(format!("Invalid vector component {component:?}")),
+            })
+            .collect::<Result<Vec<f32>, String>>()
+            .map(Vector)
+    }
+
+    /// The vector a key holds: vectors from `VADD`, or strings holding a JSON array of numbers.
+    pub fn of(value: &Value) -> Option<Vector> {
+        match value {
+            Value::Vector(vector) => Some(vector.clone()),
+            Value::String(data) => match json::parse(data).ok()? {
+                serde_json::Value::Array(values) => values
+                    .iter()
+                    .map(|value| value.as_f64().map(|value| value as f32).filter(|value| value.is_finite()))
+                    .collect::<Option<Vec<f32>>>()
+                    .map(Vector),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn norm(&self) -> f32 {
+        self.0.iter().map(|component| component * component).sum::<f32>().sqrt()
+    }
+}
+
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// How `VSEARCH` measures how far apart two vectors are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// One minus the cosine of the angle between them, from 0 for the same direction to 2 for opposite ones.
+    #[default]
+    Cosine,
+    /// The euclidean distance.
+    L2,
+}
+
+impl Metric {
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            // Vectors are normalized on the way in, so the dot product is the cosine.
+            Metric::Cosine => 1.0 - a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>(),
+            Metric::L2 => a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt(),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(metric: &str) -> Result<Metric, String> {
+        match metric {
+            "COSINE" => Ok(Metric::Cosine),
+            "L2" => Ok(Metric::L2),
+            _ => Err(format!("Invalid metric {metric:?}, valid values: ('COSINE', 'L2')")),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Cosine => write!(f, "COSINE"),
+            Metric::L2 => write!(f, "L2"),
+        }
+    }
+}
+
+/// A distance and the node it's to, ordered by distance.
+#[derive(Debug, Clone, Copy)]
+struct Near(f32, usize);
+
+impl Ord for Near {
+    fn cmp(&self, other: &Near) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+impl PartialOrd for Near {
+    fn partial_cmp(&self, other: &Near) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Near {
+    fn eq(&self, other: &Near) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Near {}
+
+#[derive(Debug)]
+struct Node {
+    id: String,
+    vector: Vec<f32>,
+    /// The neighbours on every layer the node is on, the bottom one first.
+    neighbors: Vec<Vec<usize>>,
+    /// Removed nodes stay in the graph as stepping stones until the next rebuild.
+    removed: bool,
+}
+
+/// The vectors of the keys an index covers, searched exactly by comparing against every one of them, or
+/// approximately through a HNSW graph when `approximate` is set.
+///
+/// HNSW ("Hierarchical Navigable Small World") links every vector to its nearest ones on a stack of ever
+/// sparser layers. A search walks greedily down from the top, so it only measures a small part of the vectors.
+#[derive(Debug)]
+pub struct VectorIndex {
+    dimensions: usize,
+    metric: Metric,
+    approximate: bool,
+    nodes: Vec<Node>,
+    ids: HashMap<String, usize>,
+    /// Where searches start, a node on the top layer.
+    entry: Option<usize>,
+    /// Picks the layers of new nodes, the same every run so results are reproducible.
+    seed: u64,
+}
+
+impl VectorIndex {
+    pub fn new(dimensions: usize, metric: Metric, approximate: bool) -> VectorIndex {
+        VectorIndex {
+            dimensions,
+            metric,
+            approximate,
+            nodes: vec![],
+            ids: HashMap::new(),
+            entry: None,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Adds a key. Vectors of other dimensions, and zero vectors when comparing by cosine, are left out.
+    pub fn add(&mut self, id: &str, vector: Vector) {
+        let vector = match self.prepare(vector) {
+            Ok(vector) => vector,
+            Err(_) => return,
+        };
+
+        self.remove(id);
+        self.insert(id.to_string(), vector);
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        if let Some(node) = self.ids.remove(id) {
+            self.nodes[node].removed = true;
+
+            // Searches slow down once removed nodes outnumber the live ones, so start over without them.
+            if self.nodes.len() > 2 * self.ids.len() + M {
+                self.rebuild();
+            }
+        }
+    }
+
+    /// The `k` nearest keys `visible` allows to `query` with their distance, nearest first. Always compares
+    /// against every vector when `exact` is set.
+    pub fn search(
+        &self,
+        query: Vector,
+        k: usize,
+        exact: bool,
+        visible: &dyn Fn(&str) -> bool,
+    ) -> Result<Vec<(&String, f32)>, String> {
+        let query = self.prepare(query)?;
+
+        let nearest: Vec<Near> = match self.approximate && !exact {
+            true => self.search_graph(&query, k, visible),
+            false => {
+                let mut nearest: Vec<Near> = self
+                    .ids
+                    .iter()
+                    .filter(|(id, _)| visible(id))
+                    .map(|(_, node)| node)
+                    .map(|node| Near(self.metric.distance(&query, &self.nodes[*node].vector), *node))
+                    .collect();
+
+                nearest.sort_unstable();
+                nearest.truncate(k);
+                nearest
+            }
+        };
+
+        Ok(nearest.into_iter().map(|Near(distance, node)| (&self.nodes[node].id, distance)).collect())
+    }
+
+    /// Checks the dimensions and normalizes for cosine, so the distance is a dot product.
+    fn prepare(&self, vector: Vector) -> Result<Vec<f32>, String> {
+        if vector.len() != self.dimensions {
+            return Err(format!("Invalid vector, expected {} dimensions, got {}", self.dimensions, vector.len()));
+        }
+
+        let norm = vector.norm();
+
+        match self.metric {
+            Metric::L2 => Ok(vector.0),
+            Metric::Cosine if norm == 0.0 => Err("Invalid vector, a zero vector has no direction to compare by cosine".to_string()),
+            Metric::Cosine => Ok(vector.0.into_iter().map(|component| component / norm).collect()),
+        }
+    }
+
+    fn insert(&mut self, id: String, vector: Vec<f32>) {
+        let node = self.nodes.len();
+        let layer = match self.approximate {
+            true => self.random_layer(),
+            false => 0,
+        };
+
+        self.nodes.push(Node { id: id.clone(), vector, neighbors: vec![vec![]; layer + 1], removed: false });
+        self.ids.insert(id, node);
+
+        if !self.approximate {
+            return;
+        }
+
+        let entry = match self.entry {
+            Some(entry) => entry,
+            None => {
+                self.entry = Some(node);
+                return;
+            }
+        };
+
+        let top = self.nodes[entry].neighbors.len() - 1;
+        let vector = self.nodes[node].vector.clone();
+
+        // Above the layers of the new node only the single nearest is needed, to start the next layer from.
+        let mut nearest = vec![Near(self.distance(&vector, entry), entry)];
+        for layer in (layer + 1..=top).rev() {
+            nearest = self.search_layer(&vector, nearest, 1, layer);
+        }
+
+        for layer in (0..=layer.min(top)).rev() {
+            nearest = self.search_layer(&vector, nearest, EF_CONSTRUCTION, layer);
+
+            let neighbors: Vec<usize> = nearest.iter().take(M).map(|Near(_, neighbor)| *neighbor).collect();
+            for neighbor in &neighbors {
+                self.nodes[*neighbor].neighbors[layer].push(node);
+                self.prune(*neighbor, layer);
+            }
+
+            self.nodes[node].neighbors[layer] = neighbors;
+        }
+
+        if layer > top {
+            self.entry = Some(node);
+        }
+    }
+
+    /// Keeps only the nearest neighbours of a node once it has too many.
+    fn prune(&mut self, node: usize, layer: usize) {
+        let max = if layer == 0 { 2 * M } else { M };
+        if self.nodes[node].neighbors[layer].len() <= max {
+            return;
+        }
+
+        let mut neighbors: Vec<Near> = self.nodes[node].neighbors[layer]
+            .iter()
+            .map(|neighbor| Near(self.distance(&self.nodes[node].vector, *neighbor), *neighbor))
+            .collect();
+
+        neighbors.sort_unstable();
+        self.nodes[node].neighbors[layer] = neighbors.into_iter().take(max).map(|Near(_, neighbor)| neighbor).collect();
+    }
+
+    fn search_graph(&self, query: &[f32], k: usize, visible: &dyn Fn(&str) -> bool) -> Vec<Near> {
+        let entry = match self.entry {
+            Some(entry) => entry,
+            None => return vec![],
+        };
+
+        let mut nearest = vec![Near(self.distance(query, entry), entry)];
+        for layer in (1..self.nodes[entry].neighbors.len()).rev() {
+            nearest = self.search_layer(query, nearest, 1, layer);
+        }
+
+        // There are never more than `ids` results, however many were asked for.
+        let k = k.min(self.ids.len());
+
+        // Removed nodes take up room among the candidates, so look at more of them to still find `k` live ones.
+        let ef = k.max(EF_SEARCH).saturating_mul(self.nodes.len()) / self.ids.len().max(1);
+
+        self.search_layer(query, nearest, ef, 0)
+            .into_iter()
+            .filter(|Near(_, node)| !self.nodes[*node].removed && visible(&self.nodes[*node].id))
+            .take(k)
+            .collect()
+    }
+
+    /// The `ef` nearest nodes to `query` on a layer, found by following neighbours from `start`, nearest first.
+    fn search_layer(&self, query: &[f32], start: Vec<Near>, ef: usize, layer: usize) -> Vec<Near> {
+        let mut visited: HashSet<usize> = start.iter().map(|Near(_, node)| *node).collect();
+        let mut candidates: BinaryHeap<Reverse<Near>> = start.iter().copied().map(Reverse).collect();
+        let mut found: BinaryHeap<Near> = start.into_iter().collect();
+
+        while let Some(Reverse(candidate)) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|furthest| candidate > *furthest) {
+                break;
+            }
+
+            for neighbor in &self.nodes[candidate.1].neighbors[layer] {
+                if !visited.insert(*neighbor) {
+                    continue;
+                }
+
+                let near = Near(self.distance(query, *neighbor), *neighbor);
+                if found.len() < ef || found.peek().is_some_and(|furthest| near < *furthest) {
+                    candidates.push(Reverse(near));
+                    found.push(near);
+
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    fn distance(&self, query: &[f32], node: usize) -> f32 {
+        self.metric.distance(query, &self.nodes[node].vector)
+    }
+
+    /// Draws the top layer of a new node, each one up a sixteenth as likely as the one below.
+    fn random_layer(&mut self) -> usize {
+        // xorshift64*
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        let random = (self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64;
+
+        (-(1.0 - random).ln() / (M as f64).ln()) as usize
+    }
+
+    fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.ids.clear();
+        self.entry = None;
+
+        for node in nodes.into_iter().filter(|node| !node.removed) {
+            self.insert(node.id, node.vector);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::{net::TcpListener, sync::broadcast::error::RecvError};
+
+use crate::{
+    auth::NOAUTH,
+    command::{parse_command, Command},
+    pattern,
+    server::{format_response, handle_pipe, Db, Session},
+};
+
+/// Serves websockets on a listener [`server::start`](crate::server::start) already bound, like the http gateway.
+pub async fn start(listener: TcpListener, db: Db) {
+    let router = Router::new()
+        .route("/", get(upgrade))
+        .with_state(db.clone());
+
+    let shutdown = async move { db.stopping().await };
+
+    if let Err(err) = axum::serve(listener, router).with_graceful_shutdown(shutdown).await {
+        println!("Websocket server stopped: {err}");
+    }
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(db): State<Db>) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, db))
+}
+
+/// Every text frame is one command line (pipes included) and gets exactly one reply.
+/// Ids matching a `SUB` pattern are pushed as `event` messages whenever they change.
+async fn handle_socket(socket: WebSocket, db: Db) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = db.events.subscribe();
+    let mut patterns: Vec<String> = vec![];
+    let mut session = Session::new(&db);
+
+    loop {
+        let response = tokio::select! {
+            message = receiver.next() => {
+                let line = match message {
+                    Some(Ok(Message::Text(text))) => text.trim().to_string(),
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => {
+                        println!("Failed to read: {err}.");
+                        break;
+                    }
+                };
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                let inputs = line.split("~>").map(str::trim).collect::<Vec<&str>>();
+                let result = match parse_command(line.clone()) {
+                    command @ (Command::Subscribe { .. } | Command::Unsubscribe { .. }) if inputs.len() == 1 => {
+                        match &session.user {
+                            Some(user) => user.authorize(&command).map(|_| subscribe(&mut patterns, command)),
+                            None => Err(NOAUTH.to_string()),
+                        }
+                    }
+                    _ => handle_pipe(inputs, &db, &mut session).await,
+                };
+
+                match result {
+                    Ok(response) => format_response(&db.format(), "ok", &response),
+                    Err(err) => format_response(&db.format(), "err", &err),
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) if event.database() == session.database
+                    && session.can_access(event.id())
+                    && patterns.iter().any(|p| pattern::matches(p, event.id())) => {
+                    format_response(&db.format(), "event", &event.to_string())
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = db.stopping() => {
+                let notice = format_response(&db.format(), "event", "shutdown");
+                let _ = sender.send(Message::Text(notice.into())).await;
+                let _ = sender.send(Message::Close(None)).await;
+                break;
+            }
+        };
+
+        if sender.send(Message::Text(response.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn subscribe(patterns: &mut Vec<String>, command: Command) -> String {
+    match command {
+        Command::Subscribe { pattern } => {
+            patterns.push(pattern.clone());
+            pattern
+        }
+        Command::Unsubscribe { pattern } => {
+            patterns.retain(|p| p != &pattern);
+            pattern
+        }
+        _ => unreachable!(),
+    }
+}
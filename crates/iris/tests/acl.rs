@@ -0,0 +1,70 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use std::{env, fs};
+
+const ACL: &str = r#"
+[users.admin]
+password = "admin-pass"
+
+[users.dashboard]
+password = "dash-pass"
+commands = ["GET", "LST", "CNT"]
+keys = ["metrics:*"]
+
+[users.app]
+password = "app-pass"
+commands = ["GET", "SET", "DEL"]
+keys = ["app:*"]
+"#;
+
+fn start_with_acl() -> (Server, u16) {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-acl-{port}.toml"));
+    fs::write(&path, ACL).unwrap();
+
+    let server = Server::start(&["-p", &port.to_string(), "--aclfile", path.to_str().unwrap()], port);
+
+    (server, port)
+}
+
+#[test]
+fn users_are_limited_to_their_rules() {
+    let (_server, port) = start_with_acl();
+
+    let mut admin = Connection::open(port);
+    assert_eq!(admin.send("GET app:1"), "err NOAUTH Authentication required");
+    assert_eq!(admin.send("AUTH admin admin-pass"), "ok OK");
+    assert_eq!(admin.send("SET metrics:cpu 42"), "ok metrics:cpu");
+    assert_eq!(admin.send("SET other 1"), "ok other");
+
+    let mut app = Connection::open(port);
+    assert_eq!(app.send("AUTH app wrong"), "err WRONGPASS invalid username-password pair");
+    assert_eq!(app.send("AUTH app app-pass"), "ok OK");
+    assert_eq!(app.send("SET app:1 hello"), "ok app:1");
+    assert_eq!(app.send("GET metrics:cpu"), r#"err NOPERM this user has no permissions to access the "metrics:cpu" key"#);
+    assert_eq!(app.send("LST -1"), r#"err NOPERM this user has no permissions to run the "LST" command"#);
+
+    let mut dashboard = Connection::open(port);
+    assert_eq!(dashboard.send("AUTH dashboard dash-pass"), "ok OK");
+    assert_eq!(dashboard.send("GET metrics:cpu"), "ok 42");
+    assert_eq!(dashboard.send("SET metrics:cpu 0"), r#"err NOPERM this user has no permissions to run the "SET" command"#);
+    assert_eq!(dashboard.send("LST -1"), r#"ok [("metrics:cpu", "42")]"#);
+    assert_eq!(dashboard.send("CNT -1"), "ok 1");
+}
+
+#[test]
+fn acl_whoami_and_list() {
+    let (_server, port) = start_with_acl();
+
+    let mut conn = Connection::open(port);
+    assert_eq!(conn.send("AUTH dashboard dash-pass"), "ok OK");
+    assert_eq!(conn.send("ACL WHOAMI"), "ok dashboard");
+    assert_eq!(conn.send("ACL LIST"), r#"err NOPERM this user has no permissions to run the "ACL" command"#);
+
+    assert_eq!(conn.send("AUTH admin admin-pass"), "ok OK");
+    assert_eq!(
+        conn.send("ACL LIST"),
+        r#"ok ["user admin commands=* keys=*", "user app commands=GET,SET,DEL keys=app:*", "user dashboard commands=GET,LST,CNT keys=metrics:*"]"#
+    );
+}
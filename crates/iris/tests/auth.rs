@@ -0,0 +1,33 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+
+#[test]
+fn commands_require_auth() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--requirepass", "s3cret"], port);
+
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("PING"), "err NOAUTH Authentication required");
+    assert_eq!(conn.send("SET foo bar"), "err NOAUTH Authentication required");
+    assert_eq!(conn.send("AUTH wrong"), "err WRONGPASS invalid password");
+    assert_eq!(conn.send("AUTH s3cret"), "ok OK");
+    assert_eq!(conn.send("SET foo bar"), "ok foo");
+    assert_eq!(conn.send("CONFIG GET requirepass"), r#"ok [("requirepass", "***")]"#);
+    assert!(!conn.send("CONFIG GET *").contains("s3cret"));
+
+    let mut other = Connection::open(port);
+    assert_eq!(other.send("GET foo"), "err NOAUTH Authentication required");
+}
+
+#[test]
+fn auth_without_password_configured() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("PING"), "ok PONG");
+    assert_eq!(conn.send("AUTH s3cret"), "err AUTH called without any password configured");
+}
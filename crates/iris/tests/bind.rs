@@ -0,0 +1,27 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use std::process::Command;
+
+#[test]
+fn every_bind_address_shares_one_store() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--bind", "127.0.0.1", "127.0.0.2"], port);
+
+    let mut first = Connection::connect(&format!("127.0.0.1:{port}"));
+    let mut second = Connection::connect(&format!("127.0.0.2:{port}"));
+
+    assert_eq!(first.send("SET foo bar"), "ok foo");
+    assert_eq!(second.send("GET foo"), "ok bar");
+}
+
+#[test]
+fn out_of_range_ports_are_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_iris"))
+        .args(["server", "--port", "70000"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("70000 is not in 0..=65535"));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::{connect, BitOp};
+
+#[test]
+fn bit_commands() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("SETBIT flags 1 1"), "ok 0");
+    assert_eq!(conn.send("SETBIT flags 1 1"), "ok 1");
+    assert_eq!(conn.send("SETBIT flags 10 1"), "ok 0");
+    assert_eq!(conn.send("GETBIT flags 1"), "ok 1");
+    assert_eq!(conn.send("GETBIT flags 2"), "ok 0");
+    assert_eq!(conn.send("GETBIT flags 1000"), "ok 0");
+    assert_eq!(conn.send("GETBIT missing 0"), "ok 0");
+    assert_eq!(conn.send("TYPE flags"), "ok bitmap");
+    assert_eq!(conn.send("LST -1"), r#"ok [("flags", "4020")]"#);
+    assert_eq!(conn.send("GET flags"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+
+    assert_eq!(conn.send("BITCOUNT flags"), "ok 2");
+    assert_eq!(conn.send("BITCOUNT flags 1..1"), "ok 1");
+    assert_eq!(conn.send("BITCOUNT flags 0..7 BIT"), "ok 1");
+    assert_eq!(conn.send("BITCOUNT flags -1..-1"), "ok 1");
+    assert_eq!(conn.send("BITCOUNT missing"), "ok 0");
+
+    // Strings are read as their bytes, "a" is 0b01100001.
+    assert_eq!(conn.send("SET letter a"), "ok letter");
+    assert_eq!(conn.send("BITCOUNT letter"), "ok 3");
+    assert_eq!(conn.send("GETBIT letter 1"), "ok 1");
+    assert_eq!(conn.send("SETBIT letter 0 1"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(conn.send("GET letter"), "ok a");
+
+    assert_eq!(conn.send("SETBIT flags 1 0"), "ok 1");
+    assert_eq!(conn.send("SETBIT flags 2 x"), "err Invalid bit, valid values: (0, 1)");
+}
+
+#[test]
+fn bitop_combines_into_destination() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // Daily active users, one bit per user id.
+    for (day, users) in [("day:1", [0, 2, 3]), ("day:2", [2, 3, 9])] {
+        for user in users {
+            conn.send(&format!("SETBIT {day} {user} 1"));
+        }
+    }
+
+    assert_eq!(conn.send("BITOP AND both day:1 day:2"), "ok 2");
+    assert_eq!(conn.send("BITCOUNT both"), "ok 2");
+    assert_eq!(conn.send("BITOP OR either day:1 day:2"), "ok 2");
+    assert_eq!(conn.send("BITCOUNT either"), "ok 4");
+    assert_eq!(conn.send("BITOP XOR one day:1 day:2"), "ok 2");
+    assert_eq!(conn.send("BITCOUNT one"), "ok 2");
+    assert_eq!(conn.send("BITOP NOT inverse day:1"), "ok 1");
+    assert_eq!(conn.send("BITCOUNT inverse"), "ok 5");
+
+    assert_eq!(conn.send("BITOP NOT inverse day:1 day:2"), r#"err "BITOP NOT" takes exactly one source ID"#);
+    assert_eq!(conn.send("BITOP NAND x day:1"), r#"err Invalid BITOP operation "NAND", valid values: ('AND', 'OR', 'XOR', 'NOT')"#);
+
+    // Nothing to combine deletes the destination.
+    assert_eq!(conn.send("BITOP OR both missing"), "ok 0");
+    assert_eq!(conn.send("EXISTS both"), "ok 0");
+}
+
+#[tokio::test]
+async fn client_bit_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    assert!(!client.setbit("a", 7, true).await.unwrap());
+    assert!(client.getbit("a", 7).await.unwrap());
+    client.setbit("b", 0, true).await.unwrap();
+
+    assert_eq!(client.bitop(BitOp::Or, "c", &["a", "b"]).await.unwrap(), 1);
+    assert_eq!(client.bitcount("c", None).await.unwrap(), 2);
+    assert_eq!(client.bitcount("c", Some(1..2)).await.unwrap(), 0);
+}
@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// A spawned `iris server` process that is killed when dropped.
+pub struct Server {
+    child: Child,
+}
+
+impl Server {
+    /// Starts the server with `args` and waits until `ready_port` accepts connections.
+    pub fn start(args: &[&str], ready_port: u16) -> Server {
+        let server = Server {
+            child: Command::new(env!("CARGO_BIN_EXE_iris"))
+                .arg("server")
+                .args(args)
+                .stdout(Stdio::null())
+                .spawn()
+                .expect("failed to spawn iris"),
+        };
+
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", ready_port)).is_ok() {
+                return server;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        panic!("iris did not start listening on {ready_port}");
+    }
+
+    /// Sends a signal like `HUP` or `TERM` to the server process.
+    pub fn signal(&self, name: &str) {
+        let status = Command::new("kill")
+            .arg(format!("-{name}"))
+            .arg(self.child.id().to_string())
+            .status()
+            .unwrap();
+
+        assert!(status.success(), "failed to send SIG{name}");
+    }
+
+    /// Waits up to five seconds for the server to exit on its own.
+    pub fn wait(&mut self) -> ExitStatus {
+        for _ in 0..100 {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        panic!("iris did not exit");
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// A plain tcp connection speaking the line protocol.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    pub fn open(port: u16) -> Connection {
+        Connection::connect(&format!("127.0.0.1:{port}"))
+    }
+
+    pub fn connect(addr: &str) -> Connection {
+        let writer = TcpStream::connect(addr).unwrap();
+        // `writeln!` writes the command and the newline separately, Nagle would hold back the second.
+        writer.set_nodelay(true).unwrap();
+        let reader = BufReader::new(writer.try_clone().unwrap());
+
+        Connection { reader, writer }
+    }
+
+    pub fn send(&mut self, command: &str) -> String {
+        writeln!(self.writer, "{command}").unwrap();
+        self.read_line()
+    }
+
+    pub fn read_line(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+
+        line.trim_end().to_string()
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use std::{env, fs, process::Command, thread, time::Duration};
+
+#[test]
+fn flags_override_the_config_file() {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-config-{port}.toml"));
+    fs::write(&path, "port = 1\nformat = \"json\"\nrequirepass = \"secret\"\n").unwrap();
+
+    let _server = Server::start(&["-c", path.to_str().unwrap(), "-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("PING"), r#"{"response":"NOAUTH Authentication required","status":"err"}"#);
+    assert_eq!(conn.send("AUTH secret"), r#"{"response":"OK","status":"ok"}"#);
+    assert_eq!(
+        conn.send("CONFIG GET port"),
+        format!(r#"{{"response":"[(\"port\", \"{port}\")]","status":"ok"}}"#)
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn invalid_config_files_are_rejected() {
+    let path = env::temp_dir().join(format!("iris-config-invalid-{}.toml", free_port()));
+    fs::write(&path, "colour = \"blue\"\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_iris"))
+        .args(["server", "-c", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unknown field `colour`"));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn config_set_changes_a_running_server() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(
+        conn.send("CONFIG GET *port"),
+        format!(r#"ok [("port", "{port}"), ("http-port", ""), ("ws-port", "")]"#)
+    );
+    assert_eq!(conn.send("CONFIG SET port 4000"), "err port can't be changed without a restart");
+    assert_eq!(conn.send("CONFIG SET format yaml"), r#"err Invalid format "yaml", valid values: ('default', 'json')"#);
+
+    assert_eq!(conn.send("CONFIG SET requirepass secret"), "ok OK");
+    assert_eq!(Connection::open(port).send("PING"), "err NOAUTH Authentication required");
+
+    assert_eq!(conn.send("CONFIG SET format json"), r#"{"response":"OK","status":"ok"}"#);
+    assert_eq!(conn.send("CONFIG GET format"), r#"{"response":"[(\"format\", \"json\")]","status":"ok"}"#);
+}
+
+#[test]
+fn config_reload_applies_the_file_again() {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-config-reload-{port}.toml"));
+    fs::write(&path, "debug = false\n").unwrap();
+
+    let _server = Server::start(&["-c", path.to_str().unwrap(), "-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    fs::write(&path, "debug = true\nbind = [\"0.0.0.0\"]\nport = 1\n").unwrap();
+
+    // The port flag still wins over the file, so only bind is left waiting for a restart.
+    assert_eq!(conn.send("CONFIG RELOAD"), "ok OK, restart needed for bind");
+    assert_eq!(conn.send("CONFIG GET debug"), r#"ok [("debug", "true")]"#);
+    assert_eq!(conn.send("CONFIG GET bind"), r#"ok [("bind", "127.0.0.1")]"#);
+
+    fs::write(&path, "format = \"yaml\"\n").unwrap();
+    assert_eq!(
+        conn.send("CONFIG RELOAD"),
+        r#"err Invalid format "yaml", valid values: ('default', 'json')"#
+    );
+    assert_eq!(conn.send("CONFIG GET debug"), r#"ok [("debug", "true")]"#);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn sighup_reloads_the_config_file() {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-config-sighup-{port}.toml"));
+    fs::write(&path, "").unwrap();
+
+    let server = Server::start(&["-c", path.to_str().unwrap(), "-p", &port.to_string()], port);
+
+    fs::write(&path, "requirepass = \"secret\"\n").unwrap();
+    server.signal("HUP");
+    thread::sleep(Duration::from_millis(200));
+
+    let mut conn = Connection::open(port);
+    assert_eq!(conn.send("PING"), "err NOAUTH Authentication required");
+    assert_eq!(conn.send("AUTH secret"), "ok OK");
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn config_reload_needs_a_config_file() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+
+    assert_eq!(
+        Connection::open(port).send("CONFIG RELOAD"),
+        "err No config file to reload, start the server with --config"
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::{connect_with, ConnectOptions};
+
+#[test]
+fn select_scopes_the_connection() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--databases", "2"], port);
+
+    let mut staging = Connection::open(port);
+    let mut test = Connection::open(port);
+
+    assert_eq!(test.send("SELECT 1"), "ok OK");
+    assert_eq!(staging.send("SET user:1 staging"), "ok user:1");
+    assert_eq!(test.send("SET user:1 test"), "ok user:1");
+
+    assert_eq!(staging.send("GET user:1"), "ok staging");
+    assert_eq!(test.send("GET user:1"), "ok test");
+    assert_eq!(test.send("SELECT 2"), "err DB index is out of range");
+    assert_eq!(test.send("CNT -1"), "ok 1");
+
+    assert_eq!(test.send("FLUSHDB"), "ok OK");
+    assert_eq!(test.send("CNT -1"), "ok 0");
+    assert_eq!(staging.send("GET user:1"), "ok staging");
+
+    assert_eq!(test.send("SET user:2 test"), "ok user:2");
+    assert_eq!(staging.send("FLUSHALL"), "ok OK");
+    assert_eq!(staging.send("CNT -1"), "ok 0");
+    assert_eq!(test.send("CNT -1"), "ok 0");
+}
+
+#[tokio::test]
+async fn client_connects_to_a_database() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let addr = format!("127.0.0.1:{port}");
+
+    let options = ConnectOptions { database: Some(3), ..Default::default() };
+    let mut client = connect_with(&addr, options).await.unwrap();
+    client.set("foo", "bar").await.unwrap();
+
+    let mut default = connect_with(&addr, ConnectOptions::default()).await.unwrap();
+    assert!(default.get("foo").await.is_err());
+
+    default.select(3).await.unwrap();
+    assert_eq!(default.get("foo").await.unwrap(), "bar");
+
+    client.flushdb().await.unwrap();
+    assert!(default.get("foo").await.is_err());
+
+    let out_of_range = ConnectOptions { database: Some(16), ..Default::default() };
+    assert_eq!(connect_with(&addr, out_of_range).await.unwrap_err(), "DB index is out of range");
+}
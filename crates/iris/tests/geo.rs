@@ -0,0 +1,142 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use common::{free_port, Connection, Server};
+use iris_client::{connect, GeoCenter, GeoShape};
+
+#[test]
+fn add_position_and_distance() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("GEOADD sicily 13.361389 38.115556 Palermo 15.087269 37.502669 Catania"), "ok 2");
+    assert_eq!(conn.send("GEOADD sicily 13.361389 38.115556 Palermo"), "ok 0");
+    assert_eq!(conn.send("TYPE sicily"), "ok geo");
+
+    assert_eq!(conn.send("GEOPOS sicily Palermo nowhere"), r#"ok ["13.361389 38.115556", "nil"]"#);
+    assert_eq!(conn.send("GEODIST sicily Palermo Catania"), "ok 166274.1516");
+    assert_eq!(conn.send("GEODIST sicily Palermo Catania km"), "ok 166.2742");
+    assert_eq!(conn.send("GEODIST sicily Palermo Catania MI"), "ok 103.3182");
+    assert_eq!(conn.send("GEODIST sicily Palermo nowhere"), r#"err Cannot find member "nowhere""#);
+
+    assert_eq!(conn.send("GEOADD sicily 200 38 Nowhere"), "err Invalid longitude,latitude pair 200.000000,38.000000");
+    assert_eq!(conn.send("GEOADD sicily 13 38"), r#"err "GEOADD" requires longitude latitude member triples"#);
+    assert_eq!(conn.send("GEODIST sicily Palermo Catania yd"), r#"err Invalid unit "yd", valid values: ('M', 'KM', 'FT', 'MI')"#);
+}
+
+#[test]
+fn search_by_radius_and_box() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("GEOADD sicily 13.361389 38.115556 Palermo 15.087269 37.502669 Catania");
+    conn.send("GEOADD sicily 12.758489 38.788135 edge1 17.241510 38.788135 edge2");
+
+    assert_eq!(conn.send("GEOSEARCH sicily FROMLONLAT 15 37 BYRADIUS 200 km ASC"), r#"ok ["Catania", "Palermo"]"#);
+    assert_eq!(conn.send("GEOSEARCH sicily FROMLONLAT 15 37 BYRADIUS 200 km DESC COUNT 1"), r#"ok ["Palermo"]"#);
+    assert_eq!(
+        conn.send("GEOSEARCH sicily FROMLONLAT 15 37 BYRADIUS 200 km WITHDIST"),
+        r#"ok [["Catania", "56.4413"], ["Palermo", "190.4424"]]"#
+    );
+    assert_eq!(
+        conn.send("GEOSEARCH sicily FROMMEMBER Palermo BYRADIUS 1 m WITHCOORD"),
+        r#"ok [["Palermo", "13.361389", "38.115556"]]"#
+    );
+    assert_eq!(
+        conn.send("GEOSEARCH sicily FROMLONLAT 15 37 BYBOX 400 400 km ASC"),
+        r#"ok ["Catania", "Palermo", "edge2", "edge1"]"#
+    );
+    assert_eq!(conn.send("GEOSEARCH sicily FROMLONLAT 15 37 BYBOX 150 150 km"), r#"ok ["Catania"]"#);
+    assert_eq!(conn.send("GEOSEARCH missing FROMLONLAT 15 37 BYRADIUS 1 km"), "ok []");
+
+    assert_eq!(
+        conn.send("GEOSEARCH sicily BYRADIUS 1 km"),
+        r#"err "GEOSEARCH" requires FROMMEMBER <member> or FROMLONLAT <lon> <lat>"#
+    );
+    assert_eq!(conn.send("GEOSEARCH sicily FROMMEMBER nowhere BYRADIUS 1 km"), r#"err Cannot find member "nowhere""#);
+}
+
+/// Searches only look at the geohash cells around the area, they have to find everything a full scan would.
+#[test]
+fn search_finds_what_a_full_scan_finds() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // A fixed pseudo random spread of couriers, some near the antimeridian and the poles.
+    let mut seed: u64 = 42;
+    let mut random = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    let centers = [(13.4, 52.5), (179.9, 10.0), (-0.1, 51.5), (151.2, -33.9), (25.0, 84.0)];
+    for (index, (lon, lat)) in centers.iter().enumerate() {
+        let couriers: Vec<String> = (0..400)
+            .map(|courier| {
+                let lon = (lon + (random() - 0.5) * 2.0 + 540.0) % 360.0 - 180.0;
+                let lat = (lat + (random() - 0.5) * 2.0).clamp(-85.0, 85.0);
+                format!("{lon} {lat} c{index}:{courier}")
+            })
+            .collect();
+
+        assert_eq!(conn.send(&format!("GEOADD couriers {}", couriers.join(" "))), "ok 400");
+    }
+
+    let mut checked = 0;
+    for (lon, lat) in centers {
+        let all = search(&mut conn, &format!("FROMLONLAT {lon} {lat} BYRADIUS 20000000 m WITHDIST"));
+
+        for radius in [5000.0, 15000.0, 40000.0, 90000.0, 500000.0] {
+            let expected: BTreeSet<String> = all.iter().filter(|(_, distance)| *distance <= radius).map(|(member, _)| member.clone()).collect();
+            let found: BTreeSet<String> = search(&mut conn, &format!("FROMLONLAT {lon} {lat} BYRADIUS {radius} m WITHDIST"))
+                .into_iter()
+                .map(|(member, _)| member)
+                .collect();
+
+            assert_eq!(found, expected, "radius {radius} around {lon},{lat}");
+            checked += found.len();
+        }
+    }
+
+    assert!(checked > 1000, "only {checked} couriers were in range");
+}
+
+fn search(conn: &mut Connection, options: &str) -> Vec<(String, f64)> {
+    let reply = conn.send(&format!("GEOSEARCH couriers {options}"));
+    let reply = reply.strip_prefix("ok [").unwrap().strip_suffix("]").unwrap();
+
+    reply
+        .split("], ")
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (member, distance) = pair.trim_matches(['[', ']']).split_once(", ").unwrap();
+            (member.trim_matches('"').to_string(), distance.trim_matches('"').parse().unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn client_geo_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    assert_eq!(client.geoadd("sicily", &[(13.361389, 38.115556, "Palermo"), (15.087269, 37.502669, "Catania")]).await.unwrap(), 2);
+
+    let positions = client.geopos("sicily", &["Palermo", "nowhere"]).await.unwrap();
+    assert!((positions[0].unwrap().0 - 13.361389).abs() < 0.000001);
+    assert!(positions[1].is_none());
+
+    assert!((client.geodist("sicily", "Palermo", "Catania", "km").await.unwrap() - 166.2742).abs() < 0.0001);
+
+    let found = client.geosearch("sicily", GeoCenter::LonLat(15.0, 37.0), GeoShape::Radius(200.0, "km")).await.unwrap();
+    assert_eq!(found, ["Catania", "Palermo"]);
+
+    let found = client
+        .geosearch("sicily", GeoCenter::Member("Palermo"), GeoShape::Box { width: 10.0, height: 10.0, unit: "km" }).await.unwrap();
+    assert_eq!(found, ["Palermo"]);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    process::Command,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{free_port, Server};
+
+/// Sends one http request and returns the status code and body.
+fn request(port: u16, method: &str, path: &str, authorization: Option<&str>, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let authorization = authorization.map(|value| format!("Authorization: {value}\r\n")).unwrap_or_default();
+
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{authorization}Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+
+    (status, body)
+}
+
+fn start() -> (Server, u16) {
+    let (port, http_port) = (free_port(), free_port());
+    let server = Server::start(&["-p", &port.to_string(), "--http-port", &http_port.to_string()], http_port);
+
+    (server, http_port)
+}
+
+#[test]
+fn keys() {
+    let (_server, port) = start();
+
+    assert_eq!(request(port, "PUT", "/keys/user:1", None, "joe"), (200, r#"{"response":"user:1","status":"ok"}"#.to_string()));
+    assert_eq!(request(port, "GET", "/keys/user:1", None, ""), (200, r#"{"response":"joe","status":"ok"}"#.to_string()));
+    assert_eq!(
+        request(port, "GET", "/keys/missing", None, ""),
+        (404, r#"{"response":"Cannot find item with an id of missing","status":"err"}"#.to_string())
+    );
+
+    request(port, "PUT", "/keys/order:1", None, "shoes");
+    assert_eq!(
+        request(port, "GET", "/keys?expr=-1&ns=user", None, ""),
+        (200, r#"{"response":"[(\"user:1\", \"joe\")]","status":"ok"}"#.to_string())
+    );
+    assert_eq!(request(port, "GET", "/keys?expr=x..y", None, "").0, 400);
+
+    assert_eq!(request(port, "DELETE", "/keys/user:1", None, ""), (200, r#"{"response":"joe","status":"ok"}"#.to_string()));
+    assert_eq!(request(port, "DELETE", "/keys/user:1", None, "").0, 404);
+}
+
+#[test]
+fn command() {
+    let (_server, port) = start();
+
+    assert_eq!(request(port, "POST", "/command", None, "SET a 1"), (200, r#"{"response":"a","status":"ok"}"#.to_string()));
+    assert_eq!(request(port, "POST", "/command", None, "SET b 2 ~> GET"), (200, r#"{"response":"2","status":"ok"}"#.to_string()));
+    assert_eq!(request(port, "POST", "/command", None, "NOPE"), (400, r#"{"response":"Invalid Command","status":"err"}"#.to_string()));
+    assert_eq!(request(port, "POST", "/command", None, " "), (400, r#"{"response":"Empty command","status":"err"}"#.to_string()));
+
+    // Errors get the status that fits them, not one per route.
+    assert_eq!(request(port, "POST", "/command", None, "GET missing").0, 404);
+    assert_eq!(request(port, "POST", "/command", None, "SETBIT a 1 1").0, 409);
+}
+
+#[test]
+fn full_store() {
+    let (port, http_port) = (free_port(), free_port());
+    let _server = Server::start(&["-p", &port.to_string(), "--http-port", &http_port.to_string(), "--maxmemory", "1kb"], http_port);
+
+    assert_eq!(
+        request(http_port, "PUT", "/keys/big", None, &"x".repeat(2000)),
+        (507, r#"{"response":"OOM command not allowed when used memory > 'maxmemory'","status":"err"}"#.to_string())
+    );
+}
+
+#[test]
+fn bearer_password() {
+    let (port, http_port) = (free_port(), free_port());
+    let _server = Server::start(
+        &["-p", &port.to_string(), "--http-port", &http_port.to_string(), "--requirepass", "s3cret"],
+        http_port,
+    );
+
+    assert_eq!(
+        request(http_port, "GET", "/keys", None, ""),
+        (401, r#"{"response":"NOAUTH Authentication required","status":"err"}"#.to_string())
+    );
+    assert_eq!(request(http_port, "GET", "/keys", Some("Bearer wrong"), "").0, 401);
+    assert_eq!(request(http_port, "GET", "/keys", Some("Token s3cret"), "").0, 401);
+    assert_eq!(request(http_port, "PUT", "/keys/a", Some("Bearer s3cret"), "1").0, 200);
+}
+
+#[test]
+fn basic_credentials() {
+    let (port, http_port) = (free_port(), free_port());
+    let path = env::temp_dir().join(format!("iris-http-acl-{port}.toml"));
+    fs::write(&path, "[users.app]\npassword = \"app-pass\"\nkeys = [\"app:*\"]\n").unwrap();
+
+    let _server = Server::start(
+        &["-p", &port.to_string(), "--http-port", &http_port.to_string(), "--aclfile", path.to_str().unwrap()],
+        http_port,
+    );
+
+    let basic = |credentials: &str| format!("Basic {}", STANDARD.encode(credentials));
+
+    assert_eq!(request(http_port, "PUT", "/keys/app:1", Some(&basic("app:app-pass")), "1").0, 200);
+    assert_eq!(
+        request(http_port, "PUT", "/keys/app:1", Some(&basic("app:wrong")), "1"),
+        (401, r#"{"response":"WRONGPASS invalid username-password pair","status":"err"}"#.to_string())
+    );
+    assert_eq!(request(http_port, "PUT", "/keys/app:1", Some("Basic not-base64"), "1").0, 401);
+
+    // Keys outside the user's patterns are forbidden rather than missing or a bad request.
+    let app = basic("app:app-pass");
+    assert_eq!(request(http_port, "GET", "/keys/secret", Some(&app), "").0, 403);
+    assert_eq!(request(http_port, "PUT", "/keys/secret", Some(&app), "1").0, 403);
+    assert_eq!(request(http_port, "POST", "/command", Some(&app), "GET secret").0, 403);
+}
+
+#[test]
+fn taken_port_stops_the_server() {
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let http_port = taken.local_addr().unwrap().port();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_iris"))
+        .args(["server", "-p", &free_port().to_string(), "--http-port", &http_port.to_string()])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Failed to start the http gateway"));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+/// Adds `visitor:<from>` up to `visitor:<to>` in batches.
+fn add_visitors(conn: &mut Connection, id: &str, from: usize, to: usize) {
+    for start in (from..to).step_by(1000) {
+        let elements: Vec<String> = (start..to.min(start + 1000)).map(|n| format!("visitor:{n}")).collect();
+        conn.send(&format!("PFADD {id} {}", elements.join(" ")));
+    }
+}
+
+fn count(conn: &mut Connection, ids: &str) -> f64 {
+    conn.send(&format!("PFCOUNT {ids}")).strip_prefix("ok ").unwrap().parse().unwrap()
+}
+
+#[test]
+fn estimates_within_standard_error() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // The standard error is 0.81%, three of them is 2.43%.
+    for (id, actual) in [("small", 1_000), ("medium", 10_000), ("large", 100_000), ("huge", 250_000)] {
+        add_visitors(&mut conn, id, 0, actual);
+
+        let estimate = count(&mut conn, id);
+        let error = (estimate - actual as f64).abs() / actual as f64;
+        assert!(error < 0.0243, "{id}: estimated {estimate} for {actual}, off by {:.2}%", error * 100.0);
+    }
+}
+
+#[test]
+fn counts_and_merges_across_keys() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("PFADD page:home a b c"), "ok 1");
+    assert_eq!(conn.send("PFADD page:home a b"), "ok 0");
+    assert_eq!(conn.send("PFADD page:about"), "ok 1");
+    assert_eq!(conn.send("PFADD page:about c d"), "ok 1");
+
+    assert_eq!(conn.send("PFCOUNT page:home"), "ok 3");
+    assert_eq!(conn.send("PFCOUNT page:home page:about missing"), "ok 4");
+    assert_eq!(conn.send("TYPE page:home"), "ok hyperloglog");
+
+    assert_eq!(conn.send("PFMERGE site page:home page:about"), "ok OK");
+    assert_eq!(conn.send("PFCOUNT site"), "ok 4");
+    assert_eq!(conn.send("PFADD other e"), "ok 1");
+    assert_eq!(conn.send("PFMERGE site other"), "ok OK");
+    assert_eq!(conn.send("PFCOUNT site"), "ok 5");
+
+    let wrongtype = "err WRONGTYPE Operation against a key holding the wrong kind of value";
+    assert_eq!(conn.send("SET name joe"), "ok name");
+    assert_eq!(conn.send("PFADD name a"), wrongtype);
+    assert_eq!(conn.send("PFCOUNT site name"), wrongtype);
+    assert_eq!(conn.send("GET site"), wrongtype);
+}
+
+#[test]
+fn large_unions_stay_within_standard_error() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // Two pages sharing half their 50000 visitors, 75000 distinct together.
+    add_visitors(&mut conn, "a", 0, 50_000);
+    add_visitors(&mut conn, "b", 25_000, 75_000);
+
+    let estimate = count(&mut conn, "a b");
+    assert!((estimate - 75_000.0).abs() / 75_000.0 < 0.0243, "estimated {estimate} for 75000");
+
+    conn.send("PFMERGE both a b");
+    assert_eq!(count(&mut conn, "both"), estimate);
+}
+
+#[tokio::test]
+async fn client_hyperloglog_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    assert!(client.pfadd("a", &["x", "y"]).await.unwrap());
+    assert!(client.pfadd("b", &["y", "z"]).await.unwrap());
+    assert_eq!(client.pfcount(&["a", "b"]).await.unwrap(), 3);
+
+    client.pfmerge("c", &["a", "b"]).await.unwrap();
+    assert_eq!(client.pfcount(&["c"]).await.unwrap(), 3);
+}
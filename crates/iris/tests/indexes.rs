@@ -0,0 +1,102 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+#[test]
+fn find_by_value_and_range() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send(r#"SET user:1 {"name": "anna", "age": 31, "city": "Palermo"}"#);
+    conn.send(r#"JSET user:2 $ {"name": "ben", "age": 17, "city": "Catania"}"#);
+
+    assert_eq!(conn.send("INDEX CREATE by_city ON user:* FIELD $.city"), "ok OK");
+    assert_eq!(conn.send("INDEX CREATE by_age ON user:* FIELD $.age"), "ok OK");
+
+    conn.send(r#"JSET user:3 $ {"name": "cleo", "age": 45, "city": "Palermo"}"#);
+    conn.send(r#"SET order:1 {"city": "Palermo"}"#);
+    conn.send("SET user:4 not json");
+
+    assert_eq!(conn.send("FIND by_city Palermo"), r#"ok ["user:1", "user:3"]"#);
+    assert_eq!(conn.send(r#"FIND by_city "Catania""#), r#"ok ["user:2"]"#);
+    assert_eq!(conn.send("FIND by_city Rome"), "ok []");
+
+    assert_eq!(conn.send("FIND by_age 18..45"), r#"ok ["user:1", "user:3"]"#);
+    assert_eq!(conn.send("FIND by_age 18.."), r#"ok ["user:1", "user:3"]"#);
+    assert_eq!(conn.send("FIND by_age ..31"), r#"ok ["user:2", "user:1"]"#);
+    assert_eq!(conn.send("FIND by_age 31"), r#"ok ["user:1"]"#);
+    assert_eq!(conn.send("FIND by_age 50..10"), "ok []");
+    assert_eq!(conn.send("FIND by_city A..D"), r#"ok ["user:2"]"#);
+
+    assert_eq!(conn.send("INDEX LIST"), r#"ok ["by_age ON user:* FIELD $.age", "by_city ON user:* FIELD $.city"]"#);
+}
+
+#[test]
+fn kept_up_to_date() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("INDEX CREATE by_status ON job:* FIELD $.status");
+    conn.send(r#"JSET job:1 $ {"status": "queued"}"#);
+    conn.send(r#"JSET job:2 $ {"status": "queued"}"#);
+    assert_eq!(conn.send("FIND by_status queued"), r#"ok ["job:1", "job:2"]"#);
+
+    conn.send(r#"JSET job:1 $.status "done""#);
+    assert_eq!(conn.send("FIND by_status queued"), r#"ok ["job:2"]"#);
+    assert_eq!(conn.send("FIND by_status done"), r#"ok ["job:1"]"#);
+
+    conn.send(r#"SET job:2 {"status": "done"}"#);
+    assert_eq!(conn.send("FIND by_status done"), r#"ok ["job:1", "job:2"]"#);
+
+    conn.send("JDEL job:1 $.status");
+    conn.send("RENAME job:2 job:3");
+    assert_eq!(conn.send("FIND by_status done"), r#"ok ["job:3"]"#);
+
+    conn.send("DEL job:3");
+    assert_eq!(conn.send("FIND by_status done"), "ok []");
+
+    conn.send(r#"JSET job:4 $ {"status": "queued"}"#);
+    conn.send("FLUSHDB");
+    assert_eq!(conn.send("FIND by_status queued"), "ok []");
+
+    assert_eq!(conn.send("INDEX DROP by_status"), "ok 1");
+    assert_eq!(conn.send("INDEX DROP by_status"), "ok 0");
+    assert_eq!(conn.send("FIND by_status done"), "err Cannot find index by_status");
+}
+
+#[test]
+fn errors() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("INDEX CREATE by_age ON user:* FIELD $.age");
+
+    assert_eq!(conn.send("INDEX CREATE by_age ON user:* FIELD $.age"), "err Index by_age already exists");
+    assert_eq!(conn.send("INDEX CREATE by_age ON user:*"), r#"err "INDEX CREATE" requires a Name, ON <pattern> and FIELD <path>, TEXT [FIELD <path>] or VECTOR <dimensions>"#);
+    assert_eq!(conn.send("INDEX CREATE by_name ON user:* FIELD name"), r#"err Invalid path "name", expected one like $.user.tags[0]"#);
+    assert_eq!(conn.send("INDEX"), r#"err "INDEX" requires a Subcommand ('CREATE', 'DROP', 'LIST')"#);
+
+    assert_eq!(conn.send("FIND by_age .."), r#"err Invalid range "..", it needs at least one end"#);
+    assert_eq!(conn.send("FIND by_age 1..z"), r#"err Invalid range "1..z", both ends need the same type"#);
+    assert_eq!(conn.send("FIND by_age null"), r#"err Invalid value "null", only strings, numbers and booleans are indexed"#);
+    assert_eq!(conn.send("FIND by_age"), r#"err "FIND" requires an Index and a Value or Range"#);
+}
+
+#[tokio::test]
+async fn client() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.index_create("by_city", "user:*", "$.city").await.unwrap();
+    client.set("user:1", r#"{"city": "Palermo", "age": 31}"#).await.unwrap();
+    client.set("user:2", r#"{"city": "Catania", "age": 17}"#).await.unwrap();
+
+    assert_eq!(client.find("by_city", "Palermo").await.unwrap(), vec!["user:1"]);
+    assert_eq!(client.index_list().await.unwrap(), vec!["by_city ON user:* FIELD $.city"]);
+    assert!(client.index_drop("by_city").await.unwrap());
+}
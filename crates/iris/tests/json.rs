@@ -0,0 +1,104 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+#[test]
+fn set_get_and_delete_paths() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send(r#"JSET user:1 $ {"name": "anna", "tags": ["a", "b"], "address": {"city": "Palermo"}}"#), "ok OK");
+    assert_eq!(conn.send("TYPE user:1"), "ok json");
+
+    assert_eq!(conn.send("JGET user:1 $.name"), r#"ok "anna""#);
+    assert_eq!(conn.send("JGET user:1 $.tags[-1]"), r#"ok "b""#);
+    assert_eq!(conn.send("JGET user:1 $.address"), r#"ok {"city":"Palermo"}"#);
+
+    assert_eq!(conn.send("JSET user:1 $.address.zip 90100"), "ok OK");
+    assert_eq!(conn.send(r#"JSET user:1 $.tags[0] "z""#), "ok OK");
+    assert_eq!(conn.send(r#"JSET user:1 $["odd.key"] null"#), "ok OK");
+    assert_eq!(
+        conn.send("JGET user:1"),
+        r#"ok {"address":{"city":"Palermo","zip":90100},"name":"anna","odd.key":null,"tags":["z","b"]}"#
+    );
+
+    assert_eq!(conn.send("JDEL user:1 $.address.city"), "ok 1");
+    assert_eq!(conn.send("JDEL user:1 $.address.city"), "ok 0");
+    assert_eq!(conn.send("JDEL user:1 $.tags[0]"), "ok 1");
+    assert_eq!(conn.send("JGET user:1 $.tags"), r#"ok ["b"]"#);
+
+    assert_eq!(conn.send("JDEL user:1"), "ok 1");
+    assert_eq!(conn.send("EXISTS user:1"), "ok 0");
+    assert_eq!(conn.send("JDEL user:1"), "ok 0");
+}
+
+#[test]
+fn arrays_and_numbers() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send(r#"JSET cart $ {"items": [], "total": 10, "weight": 1.5}"#);
+
+    assert_eq!(conn.send(r#"JARRAPPEND cart $.items "apple" {"name": "pear", "count": 2}"#), "ok 2");
+    assert_eq!(conn.send("JARRAPPEND cart $.items 3"), "ok 3");
+    assert_eq!(conn.send("JGET cart $.items"), r#"ok ["apple",{"count":2,"name":"pear"},3]"#);
+
+    assert_eq!(conn.send("JNUMINCRBY cart $.total 5"), "ok 15");
+    assert_eq!(conn.send("JNUMINCRBY cart $.total -20"), "ok -5");
+    assert_eq!(conn.send("JNUMINCRBY cart $.weight 0.25"), "ok 1.75");
+    assert_eq!(conn.send("JNUMINCRBY cart $.total 0.5"), "ok -4.5");
+
+    assert_eq!(conn.send("JARRAPPEND cart $.total 1"), "err Path $.total is a number, not an array");
+    assert_eq!(conn.send("JNUMINCRBY cart $.items 1"), "err Path $.items is an array, not a number");
+    assert_eq!(conn.send("JNUMINCRBY cart $.missing 1"), "err Path $.missing does not exist");
+    assert_eq!(conn.send("JNUMINCRBY cart $.total one"), r#"err Invalid number "one""#);
+
+    conn.send(r#"JSET big $ {"n": 9223372036854775807}"#);
+    assert_eq!(conn.send("JNUMINCRBY big $.n 1"), "err Incrementing $.n would overflow");
+}
+
+#[test]
+fn validates_on_write() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert!(conn.send(r#"JSET doc $ {"unclosed": 1"#).starts_with("err Invalid JSON: "));
+    assert!(conn.send("JSET doc $ nope").starts_with("err Invalid JSON: "));
+    assert_eq!(conn.send("EXISTS doc"), "ok 0");
+
+    assert_eq!(conn.send("JSET doc $.a 1"), "err Cannot find item with an id of doc, set $ first");
+    assert_eq!(conn.send("JSET doc $ [1, 2]"), "ok OK");
+    assert_eq!(conn.send("JSET doc $[5] 1"), "err Path $[5] does not exist");
+    assert_eq!(conn.send("JSET doc $.a 1"), "err Path $.a does not exist");
+    assert!(conn.send("JARRAPPEND doc $ 3 {").starts_with("err Invalid JSON: "));
+    assert_eq!(conn.send("JGET doc"), "ok [1,2]");
+
+    assert_eq!(conn.send("JGET doc items"), r#"err Invalid path "items", expected one like $.user.tags[0]"#);
+    assert_eq!(conn.send("JGET doc $."), r#"err Invalid path "$.", expected one like $.user.tags[0]"#);
+    assert_eq!(conn.send("JSET doc"), r#"err "JSET" requires an ID, a Path and a Value"#);
+
+    conn.send("SET plain hello");
+    assert_eq!(conn.send("JGET plain"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(conn.send("JDEL plain"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(conn.send("GET doc"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+}
+
+#[tokio::test]
+async fn client() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.jset("user:1", "$", r#"{"name": "anna", "visits": 1, "tags": []}"#).await.unwrap();
+    client.jset("user:1", "$.name", r#""ben""#).await.unwrap();
+
+    assert_eq!(client.jget("user:1", "$.name").await.unwrap(), r#""ben""#);
+    assert_eq!(client.jarrappend("user:1", "$.tags", &[r#""new""#, "2"]).await.unwrap(), 2);
+    assert_eq!(client.jnumincrby("user:1", "$.visits", 2.0).await.unwrap(), 3.0);
+    assert!(client.jdel("user:1", "$.tags").await.unwrap());
+    assert_eq!(client.jget("user:1", "$").await.unwrap(), r#"{"name":"ben","visits":3}"#);
+}
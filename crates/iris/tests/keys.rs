@@ -0,0 +1,56 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::{connect, Expression};
+
+#[test]
+fn key_management_commands() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("SET user:1 joe"), "ok user:1");
+    assert_eq!(conn.send("SET user:2 ann"), "ok user:2");
+
+    assert_eq!(conn.send("EXISTS user:1 user:2 user:3"), "ok 2");
+    assert_eq!(conn.send("TYPE user:1"), "ok string");
+    assert_eq!(conn.send("TYPE user:3"), "ok none");
+
+    assert_eq!(conn.send("RENAMENX user:1 user:2"), "ok 0");
+    assert_eq!(conn.send("RENAMENX user:1 user:3"), "ok 1");
+    assert_eq!(conn.send("GET user:3"), "ok joe");
+    assert_eq!(conn.send("EXISTS user:1"), "ok 0");
+
+    assert_eq!(conn.send("RENAME user:3 user:2"), "ok OK");
+    assert_eq!(conn.send("GET user:2"), "ok joe");
+    assert_eq!(conn.send("RENAME user:9 user:1"), "err Cannot find item with an id of user:9");
+
+    assert_eq!(conn.send("COPY user:2 backup:2"), "ok 1");
+    assert_eq!(conn.send("SET user:2 changed"), "ok user:2");
+    assert_eq!(conn.send("COPY user:2 backup:2"), "ok 0");
+    assert_eq!(conn.send("GET backup:2"), "ok joe");
+    assert_eq!(conn.send("COPY user:2 backup:2 REPLACE"), "ok 1");
+    assert_eq!(conn.send("GET backup:2"), "ok changed");
+
+    assert_eq!(conn.send("KEYS user:*"), r#"ok ["user:2"]"#);
+    assert_eq!(conn.send("CNT -1 IN backup"), "ok 1");
+}
+
+#[tokio::test]
+async fn client_key_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.set("a", "1").await.unwrap();
+    assert!(client.exists("a").await.unwrap());
+    assert!(client.copy("a", "b", false).await.unwrap());
+    assert!(!client.rename_nx("a", "b").await.unwrap());
+    client.rename("a", "c").await.unwrap();
+    assert_eq!(client.key_type("c").await.unwrap(), "string");
+
+    let mut keys = client.keys(Expression::Number(-1)).await.unwrap();
+    keys.sort();
+    assert_eq!(keys, ["b", "c"]);
+    assert_eq!(client.keys_matching("c*").await.unwrap(), ["c"]);
+}
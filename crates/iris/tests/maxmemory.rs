@@ -0,0 +1,114 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+
+/// Every key in these tests takes up roughly 300 bytes, so three of them fit into 1kb.
+fn start(policy: &str) -> (Server, Connection) {
+    let port = free_port();
+    let server = Server::start(
+        &["-p", &port.to_string(), "--maxmemory", "1kb", "--maxmemory-policy", policy],
+        port,
+    );
+
+    let mut conn = Connection::open(port);
+    for id in ["k1", "k2", "k3"] {
+        assert_eq!(conn.send(&format!("SET {id} {}", "x".repeat(200))), format!("ok {id}"));
+    }
+
+    (server, conn)
+}
+
+#[test]
+fn noeviction_refuses_writes_over_the_limit() {
+    let (_server, mut conn) = start("noeviction");
+
+    assert_eq!(
+        conn.send(&format!("SET k4 {}", "x".repeat(200))),
+        "err OOM command not allowed when used memory > 'maxmemory'"
+    );
+    assert_eq!(conn.send("CNT -1"), "ok 3");
+
+    // Copies and longer ids count against the limit too.
+    let long = "k".repeat(200);
+    assert_eq!(conn.send("COPY k1 k4"), "err OOM command not allowed when used memory > 'maxmemory'");
+    assert_eq!(conn.send(&format!("RENAME k1 {long}")), "err OOM command not allowed when used memory > 'maxmemory'");
+    assert_eq!(conn.send("RENAME k1 k0"), "ok OK");
+    assert_eq!(conn.send("RENAME k0 k1"), "ok OK");
+    assert_eq!(conn.send("COPY k1 k2"), "ok 0");
+    assert_eq!(conn.send("CNT -1"), "ok 3");
+
+    // Overwriting with something smaller still fits.
+    assert_eq!(conn.send("SET k1 small"), "ok k1");
+
+    // volatile-ttl has nothing to evict since keys don't expire.
+    assert_eq!(conn.send("CONFIG SET maxmemory-policy volatile-ttl"), "ok OK");
+    assert_eq!(conn.send("CONFIG GET maxmemory-policy"), r#"ok [("maxmemory-policy", "volatile-ttl")]"#);
+    assert!(conn.send(&format!("SET k4 {}", "x".repeat(400))).starts_with("err OOM"));
+
+    assert_eq!(conn.send("CONFIG SET maxmemory-policy allkeys-random"), "ok OK");
+    assert_eq!(conn.send(&format!("SET k4 {}", "x".repeat(400))), "ok k4");
+    assert_eq!(conn.send("GET k4"), format!("ok {}", "x".repeat(400)));
+}
+
+#[test]
+fn allkeys_lru_evicts_the_least_recently_used_key() {
+    let (_server, mut conn) = start("allkeys-lru");
+
+    assert!(conn.send("GET k1").starts_with("ok"));
+    assert_eq!(conn.send(&format!("SET k4 {}", "x".repeat(200))), "ok k4");
+
+    assert_eq!(conn.send("GET k2"), "err Cannot find item with an id of k2");
+    assert!(conn.send("GET k1").starts_with("ok"));
+    assert_eq!(conn.send("CNT -1"), "ok 3");
+}
+
+#[test]
+fn the_key_being_written_is_never_evicted() {
+    let (_server, mut conn) = start("allkeys-lru");
+
+    // k1 is the least recently used, but growing it has to evict another key.
+    assert_eq!(conn.send(&format!("SET k1 {}", "x".repeat(400))), "ok k1");
+    assert_eq!(conn.send("GET k1"), format!("ok {}", "x".repeat(400)));
+    assert_eq!(conn.send("GET k2"), "err Cannot find item with an id of k2");
+    assert!(conn.send("GET k3").starts_with("ok"));
+}
+
+#[test]
+fn allkeys_lfu_evicts_the_least_frequently_used_key() {
+    let (_server, mut conn) = start("allkeys-lfu");
+
+    for command in ["GET k1", "GET k1", "GET k3"] {
+        assert!(conn.send(command).starts_with("ok"));
+    }
+
+    assert_eq!(conn.send(&format!("SET k4 {}", "x".repeat(200))), "ok k4");
+    assert_eq!(conn.send("GET k2"), "err Cannot find item with an id of k2");
+    assert_eq!(conn.send("CNT -1"), "ok 3");
+}
+
+#[test]
+fn allkeys_lru_samples_when_there_are_many_keys() {
+    let (_server, mut conn) = start("allkeys-lru");
+    assert_eq!(conn.send("CONFIG SET maxmemory 64kb"), "ok OK");
+
+    // Far more keys than fit, the one read before every write is always the newest of any sample.
+    for id in 0..2000 {
+        assert!(conn.send("GET k1").starts_with("ok"));
+        assert_eq!(conn.send(&format!("SET key:{id} {}", "x".repeat(100))), format!("ok key:{id}"));
+    }
+
+    assert!(conn.send("GET k1").starts_with("ok"));
+    assert!(conn.send("CNT -1").trim_start_matches("ok ").parse::<usize>().unwrap() < 2000);
+}
+
+#[test]
+fn invalid_limits_are_rejected() {
+    let (_server, mut conn) = start("noeviction");
+
+    assert_eq!(
+        conn.send("CONFIG SET maxmemory lots"),
+        r#"err Invalid maxmemory "lots", expected bytes like 1048576, 512kb, 100mb or 2gb"#
+    );
+    assert_eq!(conn.send("CONFIG SET maxmemory 2MB"), "ok OK");
+    assert_eq!(conn.send("CONFIG GET maxmemory*"), r#"ok [("maxmemory", "2097152"), ("maxmemory-policy", "noeviction")]"#);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+
+#[test]
+fn namespaces_are_indexed_on_set_and_del() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    for id in ["user:2", "user:1", "order:eu:1", "order:us:2", "plain"] {
+        assert_eq!(conn.send(&format!("SET {id} {id}")), format!("ok {id}"));
+    }
+
+    assert_eq!(
+        conn.send("NS LIST"),
+        r#"ok [("order", 2), ("order:eu", 1), ("order:us", 1), ("user", 2)]"#
+    );
+    assert_eq!(conn.send("CNT -1 IN order"), "ok 2");
+    assert_eq!(conn.send("LST -1 IN user"), r#"ok [("user:1", "user:1"), ("user:2", "user:2")]"#);
+    assert_eq!(conn.send("LST 1 IN order:us"), r#"ok [("order:us:2", "order:us:2")]"#);
+    assert_eq!(conn.send("CNT -1 IN missing"), "ok 0");
+
+    assert_eq!(conn.send("DEL user:1"), "ok user:1");
+    assert_eq!(conn.send("CNT -1 IN user"), "ok 1");
+
+    assert_eq!(conn.send("NS DROP order"), "ok 2");
+    assert_eq!(conn.send("NS LIST"), r#"ok [("user", 1)]"#);
+    assert_eq!(conn.send("CNT -1"), "ok 2");
+}
+
+#[test]
+fn the_delimiter_is_configurable() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--namespace-delimiter", "/"], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("SET cache/pages/home html"), "ok cache/pages/home");
+    assert_eq!(conn.send("SET user:1 joe"), "ok user:1");
+
+    assert_eq!(conn.send("NS LIST"), r#"ok [("cache", 1), ("cache/pages", 1)]"#);
+    assert_eq!(conn.send("CONFIG GET namespace-delimiter"), r#"ok [("namespace-delimiter", "/")]"#);
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use std::{
+    env, fs, thread,
+    time::{Duration, Instant},
+};
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+const INCR: &str = r#"{ let n = parse_int(call("GET", KEYS[0])); call("SET", KEYS[0], n + parse_int(ARGS[0])); n + parse_int(ARGS[0]) }"#;
+
+#[test]
+fn eval_calls_commands() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("SET visits 41");
+    assert_eq!(conn.send(&format!("EVAL {INCR} 1 visits 1")), "ok 42");
+    assert_eq!(conn.send("GET visits"), "ok 42");
+
+    assert_eq!(conn.send(r#"EVAL { [KEYS, ARGS] } 2 a b c"#), r#"ok ["[\"a\", \"b\"]", "[\"c\"]"]"#);
+    assert_eq!(conn.send(r#"EVAL { call("SET", "greeting", "hello { world }") } 0"#), "ok greeting");
+    assert_eq!(conn.send("GET greeting"), "ok hello { world }");
+    assert_eq!(conn.send(r#"EVAL { let x = 1; } 0"#), "ok OK");
+    assert_eq!(conn.send(r#"EVAL { call("TYPE", "visits") == "string" } 0"#), "ok true");
+}
+
+#[test]
+fn args_are_passed_as_they_are() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // The line protocol would split these up or pipe them, a script's args reach the command whole.
+    let value = r#"  two  spaces, "quotes" and a pipe "#;
+    assert_eq!(
+        conn.send(r#"EVAL { call("SET", "odd", "  two  spaces, \"quotes\" and a pipe " + "~" + ">") } 0"#),
+        "ok odd"
+    );
+    assert_eq!(conn.send(r#"EVAL { call("GET", "odd") } 0"#), format!("ok {value}~>"));
+}
+
+#[test]
+fn only_commands_on_keys_wait_for_scripts() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--script-time-limit", "1000"], port);
+
+    let script = thread::spawn(move || Connection::open(port).send("EVAL { loop {} } 0"));
+    thread::sleep(Duration::from_millis(200));
+
+    let mut conn = Connection::open(port);
+    let started = Instant::now();
+    assert_eq!(conn.send("PING"), "ok PONG");
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    assert_eq!(conn.send("SET after 1"), "ok after");
+    assert!(started.elapsed() >= Duration::from_millis(500));
+    assert_eq!(script.join().unwrap(), "err Script exceeded the time limit of 1000ms");
+}
+
+#[test]
+fn errors() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send(r#"EVAL { call("GET", "missing") } 0"#), "err Cannot find item with an id of missing");
+    assert_eq!(conn.send(r#"EVAL { try { return call("GET", "missing") } catch (err) { return "none" } } 0"#), "ok none");
+    assert_eq!(conn.send(r#"EVAL { throw "nope" } 0"#), "err nope");
+    assert_eq!(conn.send(r#"EVAL { call("SHUTDOWN") } 0"#), "err SHUTDOWN can't be called from a script");
+    assert_eq!(conn.send(r#"EVAL { call("EVAL", "{", "1", "}", "0") } 0"#), "err EVAL can't be called from a script");
+    assert_eq!(conn.send(r#"EVAL { call("SELECT", 1) } 0"#), "err SELECT can't be called from a script");
+    assert!(conn.send(r#"EVAL { let = } 0"#).starts_with("err Invalid script, "));
+    assert!(conn.send(r#"EVAL { undefined_function() } 0"#).starts_with("err Script error: "));
+
+    assert_eq!(conn.send("EVAL"), r#"err "EVAL" requires a Script in braces"#);
+    assert_eq!(conn.send("EVAL 1 0"), r#"err "EVAL" requires a Script in braces"#);
+    assert_eq!(conn.send(r#"EVAL { "}" 0"#), "err Invalid script, missing }");
+    assert_eq!(conn.send("EVAL { 1 }"), r#"err "EVAL" requires a Number of keys"#);
+    assert_eq!(conn.send("EVAL { 1 } x"), r#"err Invalid number of keys "x""#);
+    assert_eq!(conn.send("EVAL { 1 } 2 a"), "err Number of keys can't be greater than number of args");
+    assert_eq!(conn.send("SCRIPT"), r#"err "SCRIPT" requires a Subcommand ('LOAD')"#);
+    assert_eq!(conn.send("SCRIPT LOAD"), r#"err "SCRIPT LOAD" requires a Script in braces"#);
+    assert_eq!(conn.send("EVALSHA abc"), r#"err "EVALSHA" requires a SHA and a Number of keys"#);
+}
+
+#[test]
+fn limits() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // Growing values stop at their limit long before the time limit.
+    let started = Instant::now();
+    assert!(conn.send(r#"EVAL { let s = "x"; loop { s += s; } } 0"#).starts_with("err Script error: Length of string too large"));
+    assert!(conn.send(r#"EVAL { let a = [1]; loop { a += a; } } 0"#).starts_with("err Script error: Size of array/BLOB too large"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    let nested = format!("EVAL {{ {}1{} }} 0", "(".repeat(100), ")".repeat(100));
+    assert!(conn.send(&nested).starts_with("err Invalid script, "));
+
+    let long = format!("SCRIPT LOAD {{ \"{}\" }}", "x".repeat(64 << 10));
+    assert_eq!(conn.send(&long), "err Invalid script, longer than 65536 bytes");
+
+    // Only the last 1000 compiled scripts are kept.
+    let first = conn.send("SCRIPT LOAD { 0 }").trim_start_matches("ok ").to_string();
+    for n in 1..=1000 {
+        conn.send(&format!("SCRIPT LOAD {{ {n} }}"));
+    }
+    assert_eq!(conn.send(&format!("EVALSHA {first} 0")), "err NOSCRIPT No matching script, use SCRIPT LOAD");
+    assert_eq!(conn.send("EVAL { 1000 } 0"), "ok 1000");
+}
+
+#[test]
+fn script_load_and_evalsha() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    let sha = conn.send(r#"SCRIPT LOAD { call("GET", KEYS[0]) + ARGS[0] }"#);
+    assert_eq!(sha, "ok 4443cf84adf3fa43739beb5f2ec180c1a3936b9b");
+    let sha = sha.trim_start_matches("ok ");
+
+    conn.send("SET name iris");
+    assert_eq!(conn.send(&format!("EVALSHA {sha} 1 name !")), "ok iris!");
+    assert_eq!(conn.send(&format!("EVALSHA {sha} 1 name ?")), "ok iris?");
+
+    assert_eq!(conn.send("EVALSHA 0000 0"), "err NOSCRIPT No matching script, use SCRIPT LOAD");
+
+    // EVAL caches what it compiled, so EVALSHA runs it after.
+    conn.send("EVAL { 7 } 0");
+    assert_eq!(conn.send("EVALSHA acf80c9cf69a5731963832daa04ca470763f210f 0"), "ok 7");
+}
+
+#[test]
+fn scripts_run_atomically() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    Connection::open(port).send("SET counter 0");
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut conn = Connection::open(port);
+
+                for _ in 0..50 {
+                    assert!(conn.send(&format!("EVAL {INCR} 1 counter 1")).starts_with("ok "));
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert_eq!(Connection::open(port).send("GET counter"), "ok 200");
+}
+
+#[test]
+fn time_limit() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string(), "--script-time-limit", "100"], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send(r#"EVAL { call("SET", "before", 1); loop {} } 0"#), "err Script exceeded the time limit of 100ms");
+    assert_eq!(conn.send("GET before"), "ok 1");
+
+    assert_eq!(conn.send("CONFIG SET script-time-limit 50"), "ok OK");
+    assert_eq!(conn.send("CONFIG GET script-time-limit"), r#"ok [("script-time-limit", "50")]"#);
+    assert_eq!(conn.send("EVAL { loop {} } 0"), "err Script exceeded the time limit of 50ms");
+    assert_eq!(conn.send("CONFIG SET script-time-limit soon"), r#"err Invalid script-time-limit "soon", expected milliseconds"#);
+}
+
+#[test]
+fn scripts_follow_acl_rules() {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-scripting-acl-{port}.toml"));
+    fs::write(
+        &path,
+        r#"
+        [users.admin]
+        password = "admin-pass"
+
+        [users.app]
+        password = "app-pass"
+        commands = ["EVAL", "GET"]
+        keys = ["app:*"]
+        "#,
+    )
+    .unwrap();
+
+    let _server = Server::start(&["-p", &port.to_string(), "--aclfile", path.to_str().unwrap()], port);
+
+    let mut admin = Connection::open(port);
+    admin.send("AUTH admin admin-pass");
+    admin.send("SET app:1 hello");
+    admin.send("SET secret 1");
+
+    let mut app = Connection::open(port);
+    assert_eq!(app.send("AUTH app app-pass"), "ok OK");
+    assert_eq!(app.send(r#"EVAL { call("GET", KEYS[0]) } 1 app:1"#), "ok hello");
+    assert_eq!(
+        app.send(r#"EVAL { call("GET", KEYS[0]) } 1 secret"#),
+        r#"err NOPERM this user has no permissions to access the "secret" key"#
+    );
+    assert_eq!(
+        app.send(r#"EVAL { call("GET", "secret") } 0"#),
+        r#"err NOPERM this user has no permissions to access the "secret" key"#
+    );
+    assert_eq!(
+        app.send(r#"EVAL { call("SET", "app:1", "x") } 0"#),
+        r#"err NOPERM this user has no permissions to run the "SET" command"#
+    );
+    assert_eq!(
+        app.send(r#"SCRIPT LOAD { 1 }"#),
+        r#"err NOPERM this user has no permissions to run the "SCRIPT" command"#
+    );
+}
+
+#[tokio::test]
+async fn client() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.set("visits", "1").await.unwrap();
+    assert_eq!(client.eval(INCR, &["visits"], &["2"]).await.unwrap(), "3");
+
+    let sha = client.script_load(INCR).await.unwrap();
+    assert_eq!(client.evalsha(&sha, &["visits"], &["10"]).await.unwrap(), "13");
+    assert_eq!(client.evalsha("0000", &[], &[]).await.unwrap_err(), "NOSCRIPT No matching script, use SCRIPT LOAD");
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+fn notes(conn: &mut Connection) {
+    conn.send("SET note:1 Wireless mouse with a long battery life");
+    conn.send("SET note:2 Mouse pad, large");
+    conn.send("SET note:3 Mechanical keyboard with wireless receiver and wireless charging");
+    conn.send("SET note:4 Battery charger for AA batteries");
+}
+
+#[test]
+fn terms_prefixes_and_booleans() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    notes(&mut conn);
+    assert_eq!(conn.send("INDEX CREATE notes ON note:* TEXT"), "ok OK");
+
+    assert_eq!(conn.send("SEARCH notes wireless"), r#"ok ["note:3", "note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes WIRELESS"), r#"ok ["note:3", "note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes mouse wireless"), r#"ok ["note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes mouse AND wireless"), r#"ok ["note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes mouse OR keyboard"), r#"ok ["note:3", "note:2", "note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes batter*"), r#"ok ["note:4", "note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes wireless -keyboard"), r#"ok ["note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes NOT mouse"), r#"ok ["note:3", "note:4"]"#);
+    assert_eq!(conn.send("SEARCH notes (mouse OR charger) NOT pad"), r#"ok ["note:4", "note:1"]"#);
+    assert_eq!(conn.send("SEARCH notes trackball"), "ok []");
+
+    assert_eq!(conn.send("INDEX LIST"), r#"ok ["notes ON note:* TEXT"]"#);
+}
+
+#[test]
+fn json_fields_and_updates() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send(r#"JSET product:1 $ {"name": "Desk lamp", "description": "Warm light for reading", "tags": ["office"]}"#);
+    conn.send(r#"SET product:2 {"name": "Reading glasses", "description": "Light frames", "tags": ["optics"]}"#);
+
+    conn.send("INDEX CREATE products ON product:* TEXT");
+    conn.send("INDEX CREATE descriptions ON product:* TEXT FIELD $.description");
+
+    assert_eq!(conn.send("SEARCH products office"), r#"ok ["product:1"]"#);
+    assert_eq!(conn.send("SEARCH products reading light"), r#"ok ["product:1", "product:2"]"#);
+    assert_eq!(conn.send("SEARCH descriptions glasses"), "ok []");
+    assert_eq!(conn.send("SEARCH descriptions light"), r#"ok ["product:2", "product:1"]"#);
+
+    conn.send(r#"JSET product:1 $.description "Bright bulb""#);
+    assert_eq!(conn.send("SEARCH descriptions light"), r#"ok ["product:2"]"#);
+    assert_eq!(conn.send("SEARCH descriptions bright"), r#"ok ["product:1"]"#);
+
+    conn.send("DEL product:2");
+    assert_eq!(conn.send("SEARCH products reading"), "ok []");
+    assert_eq!(conn.send("SEARCH products NOT lamp"), "ok []");
+}
+
+#[test]
+fn errors() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("INDEX CREATE notes ON note:* TEXT");
+    conn.send("INDEX CREATE by_age ON user:* FIELD $.age");
+
+    assert_eq!(conn.send("SEARCH notes (mouse"), "err Invalid query, missing )");
+    assert_eq!(conn.send("SEARCH notes mouse OR"), "err Invalid query, it ends too early");
+    assert_eq!(conn.send("SEARCH notes mouse)"), r#"err Invalid query, unexpected ")""#);
+    assert_eq!(conn.send("SEARCH notes !!!"), r#"err Invalid query, "!!!" has no letters or digits"#);
+    assert_eq!(conn.send("SEARCH notes"), r#"err "SEARCH" requires an Index and a Query"#);
+
+    assert_eq!(conn.send("SEARCH by_age 31"), "err Index by_age isn't a text index, use FIND");
+    assert_eq!(conn.send("FIND notes mouse"), "err Index notes is a text index, use SEARCH");
+    assert_eq!(conn.send("SEARCH missing mouse"), "err Cannot find index missing");
+}
+
+#[tokio::test]
+async fn client() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.index_create_text("notes", "note:*", None).await.unwrap();
+    client.set("note:1", "Wireless mouse").await.unwrap();
+    client.set("note:2", "Wireless keyboard").await.unwrap();
+
+    assert_eq!(client.search("notes", "wireless -keyboard").await.unwrap(), vec!["note:1"]);
+    assert_eq!(client.search("notes", "key*").await.unwrap(), vec!["note:2"]);
+}
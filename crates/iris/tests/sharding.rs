@@ -0,0 +1,51 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use std::thread;
+
+#[test]
+fn concurrent_writers_across_shards_all_land() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            thread::spawn(move || {
+                let mut conn = Connection::open(port);
+
+                for key in 0..100 {
+                    assert_eq!(conn.send(&format!("SET w{writer}:{key} {key}")), format!("ok w{writer}:{key}"));
+                    assert_eq!(conn.send(&format!("GET w{writer}:{key}")), format!("ok {key}"));
+                }
+
+                // Multi-shard commands racing the other writers must not deadlock.
+                assert!(conn.send("CNT -1").starts_with("ok"));
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let mut conn = Connection::open(port);
+    assert_eq!(conn.send("CNT -1"), "ok 800");
+    assert_eq!(conn.send("DEL w3:7"), "ok 7");
+    assert_eq!(conn.send("CNT 0..-1"), "ok 799");
+    assert!(conn.send("DEL -1").starts_with("ok [("));
+    assert_eq!(conn.send("CNT -1"), "ok 0");
+}
+
+#[test]
+fn range_delete_on_an_empty_database() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+
+    let mut conn = Connection::open(port);
+    assert_eq!(conn.send("DEL 0..-1"), "ok []");
+
+    // The shards stay usable from every connection afterwards.
+    let mut other = Connection::open(port);
+    assert_eq!(other.send("SET a 1"), "ok a");
+    assert_eq!(conn.send("GET a"), "ok 1");
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use std::net::TcpStream;
+
+#[test]
+fn shutdown_notifies_every_connection_and_exits() {
+    let port = free_port();
+    let mut server = Server::start(&["-p", &port.to_string()], port);
+
+    let mut idle = Connection::open(port);
+    let mut conn = Connection::open(port);
+    assert_eq!(idle.send("SET foo bar"), "ok foo");
+
+    assert_eq!(conn.send("SHUTDOWN NOSAVE"), "ok OK");
+    assert_eq!(conn.read_line(), "event shutdown");
+    assert_eq!(idle.read_line(), "event shutdown");
+
+    assert!(server.wait().success());
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+}
+
+#[test]
+fn sigterm_shuts_down_gracefully() {
+    let port = free_port();
+    let mut server = Server::start(&["-p", &port.to_string(), "--format", "json"], port);
+    let mut conn = Connection::open(port);
+    assert_eq!(conn.send("PING"), r#"{"response":"PONG","status":"ok"}"#);
+
+    server.signal("TERM");
+
+    assert_eq!(conn.read_line(), r#"{"response":"shutdown","status":"event"}"#);
+    assert!(server.wait().success());
+}
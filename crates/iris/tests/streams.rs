@@ -0,0 +1,144 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+#[test]
+fn append_range_and_trim() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("XADD log 1-1 event signup user joe"), "ok 1-1");
+    assert_eq!(conn.send("XADD log 1-2 event login"), "ok 1-2");
+    assert_eq!(
+        conn.send("XADD log 1-2 event login"),
+        "err The ID specified in XADD is equal or smaller than the target stream top item"
+    );
+
+    // Generated ids come from the clock and keep increasing.
+    let first = conn.send("XADD log * event logout");
+    let second = conn.send("XADD log * event logout");
+    assert!(first.starts_with("ok ") && second.starts_with("ok "));
+    assert!(id(&second) > id(&first));
+
+    assert_eq!(conn.send("XLEN log"), "ok 4");
+    assert_eq!(conn.send("TYPE log"), "ok stream");
+    assert_eq!(
+        conn.send("XRANGE log - + COUNT 2"),
+        r#"ok [("1-1", [("event", "signup"), ("user", "joe")]), ("1-2", [("event", "login")])]"#
+    );
+    assert_eq!(conn.send("XRANGE log 1 1"), r#"ok [("1-1", [("event", "signup"), ("user", "joe")]), ("1-2", [("event", "login")])]"#);
+    assert_eq!(conn.send("XRANGE log 1-2 1-2"), r#"ok [("1-2", [("event", "login")])]"#);
+    assert_eq!(conn.send("XRANGE missing - +"), "ok []");
+
+    assert_eq!(conn.send("XTRIM log MAXLEN 1"), "ok 3");
+    assert_eq!(conn.send("XLEN log"), "ok 1");
+    assert_eq!(conn.send("XADD capped MAXLEN 2 1 a 1"), "ok 1-0");
+    assert_eq!(conn.send("XADD capped MAXLEN 2 2 a 2"), "ok 2-0");
+    assert_eq!(conn.send("XADD capped MAXLEN 2 3 a 3"), "ok 3-0");
+    assert_eq!(conn.send("XRANGE capped - +"), r#"ok [("2-0", [("a", "2")]), ("3-0", [("a", "3")])]"#);
+
+    // Trimming never lets an id repeat.
+    assert_eq!(conn.send("XTRIM capped MAXLEN 0"), "ok 2");
+    assert!(conn.send("XADD capped 3 a 3").starts_with("err "));
+
+    assert_eq!(conn.send("XADD log 9 odd"), r#"err "XADD" requires an entry ID and field value pairs"#);
+    assert_eq!(conn.send("SET name joe"), "ok name");
+    assert_eq!(conn.send("XADD name * a 1"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(conn.send("XADD new 0-0 a 1").get(..4), Some("err "));
+    assert_eq!(conn.send("EXISTS new"), "ok 0");
+}
+
+#[test]
+fn generated_ids_run_out_at_the_last_possible_id() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    // A full sequence moves on to the next millisecond.
+    assert_eq!(conn.send("XADD far 18446744073709551614-18446744073709551615 a 1"), "ok 18446744073709551614-18446744073709551615");
+    assert_eq!(conn.send("XADD far * a 2"), "ok 18446744073709551615-0");
+
+    assert_eq!(conn.send("XADD last 18446744073709551615-18446744073709551615 a 1"), "ok 18446744073709551615-18446744073709551615");
+    assert_eq!(conn.send("XADD last * a 2"), "err The stream has exhausted the last possible ID");
+    assert_eq!(conn.send("XLEN last"), "ok 1");
+}
+
+#[test]
+fn consumer_groups_deliver_at_least_once() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(
+        conn.send("XGROUP CREATE jobs workers $"),
+        "err Cannot find stream with an id of jobs, add MKSTREAM to create it"
+    );
+    assert_eq!(conn.send("XGROUP CREATE jobs workers $ MKSTREAM"), "ok OK");
+    assert_eq!(conn.send("XGROUP CREATE jobs workers $"), "err BUSYGROUP Consumer Group name already exists");
+
+    for job in 1..=3 {
+        conn.send(&format!("XADD jobs {job} task {job}"));
+    }
+
+    // Each new entry goes to one consumer.
+    assert_eq!(conn.send("XREADGROUP GROUP workers a COUNT 2 STREAMS jobs >"), r#"ok [("1-0", [("task", "1")]), ("2-0", [("task", "2")])]"#);
+    assert_eq!(conn.send("XREADGROUP GROUP workers b STREAMS jobs >"), r#"ok [("3-0", [("task", "3")])]"#);
+    assert_eq!(conn.send("XREADGROUP GROUP workers b STREAMS jobs >"), "ok []");
+
+    let pending = conn.send("XPENDING jobs workers");
+    assert!(pending.starts_with(r#"ok [("1-0", "a", "#), "{pending}");
+    assert_eq!(pending.matches("\"a\"").count(), 2);
+    assert_eq!(pending.matches("\"b\"").count(), 1);
+
+    // a crashes after finishing job 1, on restart it gets what it didn't acknowledge again.
+    assert_eq!(conn.send("XACK jobs workers 1-0"), "ok 1");
+    assert_eq!(conn.send("XACK jobs workers 1-0"), "ok 0");
+    assert_eq!(conn.send("XREADGROUP GROUP workers a STREAMS jobs 0"), r#"ok [("2-0", [("task", "2")])]"#);
+    assert!(conn.send("XPENDING jobs workers").contains(r#"("2-0", "a", "#));
+    assert!(conn.send("XPENDING jobs workers").contains(", 2)"));
+
+    assert_eq!(conn.send("XACK jobs workers 2-0 3-0"), "ok 2");
+    assert_eq!(conn.send("XPENDING jobs workers"), "ok []");
+
+    // A group created from 0 gets the whole history.
+    assert_eq!(conn.send("XGROUP CREATE jobs audit 0"), "ok OK");
+    assert_eq!(conn.send("XREADGROUP GROUP audit x COUNT 1 STREAMS jobs >"), r#"ok [("1-0", [("task", "1")])]"#);
+
+    assert_eq!(conn.send("XREADGROUP GROUP nope a STREAMS jobs >"), r#"err NOGROUP No such consumer group "nope""#);
+    assert_eq!(
+        conn.send("XREADGROUP GROUP workers a STREAMS missing >"),
+        r#"err NOGROUP No such key "missing" or consumer group "workers""#
+    );
+}
+
+#[tokio::test]
+async fn client_stream_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.xgroup_create("events", "workers", "$", true).await.unwrap();
+    let id = client.xadd("events", &[("kind", "signup"), ("user", "joe")]).await.unwrap();
+    client.xadd("events", &[("kind", "login")]).await.unwrap();
+    assert_eq!(client.xlen("events").await.unwrap(), 2);
+
+    let entries = client.xrange("events", "-", "+", Some(1)).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, id);
+    assert_eq!(entries[0].fields[1].id, "user");
+    assert_eq!(entries[0].fields[1].data, "joe");
+
+    let delivered = client.xreadgroup("events", "workers", "a", ">", None).await.unwrap();
+    assert_eq!(delivered.len(), 2);
+    assert_eq!(client.xack("events", "workers", &[&delivered[0].id]).await.unwrap(), 1);
+    assert_eq!(client.xreadgroup("events", "workers", "a", "0", None).await.unwrap().len(), 1);
+
+    assert_eq!(client.xtrim("events", 0).await.unwrap(), 2);
+}
+
+fn id(reply: &str) -> (u64, u64) {
+    let (ms, seq) = reply.strip_prefix("ok ").unwrap().split_once('-').unwrap();
+    (ms.parse().unwrap(), seq.parse().unwrap())
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+#[test]
+fn string_commands() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("APPEND log hello"), "ok 5");
+    assert_eq!(conn.send("APPEND log world"), "ok 10");
+    assert_eq!(conn.send("GET log"), "ok helloworld");
+    assert_eq!(conn.send("STRLEN log"), "ok 10");
+    assert_eq!(conn.send("STRLEN missing"), "ok 0");
+
+    assert_eq!(conn.send("GETRANGE log 0..4"), "ok hello");
+    assert_eq!(conn.send("GETRANGE log -5..-1"), "ok world");
+    assert_eq!(conn.send("GETRANGE log 5..100"), "ok world");
+    assert_eq!(conn.send("GETRANGE log 0"), r#"err "GETRANGE" requires a Range (0..5)"#);
+
+    assert_eq!(conn.send("SETRANGE log 5 WORLD"), "ok 10");
+    assert_eq!(conn.send("GET log"), "ok helloWORLD");
+    assert_eq!(conn.send("SETRANGE padded 2 x"), "ok 3");
+    assert_eq!(conn.send("GETRANGE padded 2..2"), "ok x");
+
+    assert_eq!(conn.send("SET word héllo"), "ok word");
+    assert_eq!(conn.send("SETRANGE word 2 x"), "err SETRANGE would split a multi-byte character");
+}
+
+#[tokio::test]
+async fn client_string_methods() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    assert_eq!(client.append("a", "abc").await.unwrap(), 3);
+    assert_eq!(client.setrange("a", 1, "X").await.unwrap(), 3);
+    assert_eq!(client.strlen("a").await.unwrap(), 3);
+    assert_eq!(client.getrange("a", 0, -1).await.unwrap(), "aXc");
+    assert_eq!(client.getrange("a", 5, 9).await.unwrap(), "");
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{free_port, Server};
+use iris_client::{connect_with, ConnectOptions, TlsOptions};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use std::{env, fs, path::PathBuf};
+
+/// A self-signed ca with a server and a client certificate signed by it, written as PEM files.
+struct Certs {
+    dir: PathBuf,
+}
+
+impl Certs {
+    fn generate(name: &str) -> Certs {
+        let dir = env::temp_dir().join(format!("iris-tls-{name}-{}", free_port()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let server_key = KeyPair::generate().unwrap();
+        let mut server_params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        server_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let server = server_params.signed_by(&server_key, &ca, &ca_key).unwrap();
+
+        let client_key = KeyPair::generate().unwrap();
+        let mut client_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client = client_params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+        let certs = Certs { dir };
+        certs.write("ca", &ca, &ca_key);
+        certs.write("server", &server, &server_key);
+        certs.write("client", &client, &client_key);
+
+        certs
+    }
+
+    fn write(&self, name: &str, cert: &Certificate, key: &KeyPair) {
+        fs::write(self.dir.join(format!("{name}.pem")), cert.pem()).unwrap();
+        fs::write(self.dir.join(format!("{name}.key")), key.serialize_pem()).unwrap();
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.dir.join(file).to_str().unwrap().to_string()
+    }
+}
+
+fn tls_options(certs: &Certs, client_identity: bool) -> ConnectOptions {
+    ConnectOptions {
+        tls: Some(TlsOptions {
+            ca_cert: Some(certs.path("ca.pem")),
+            client_cert: client_identity.then(|| certs.path("client.pem")),
+            client_key: client_identity.then(|| certs.path("client.key")),
+            server_name: Some("localhost".to_string()),
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn commands_over_tls() {
+    let certs = Certs::generate("server");
+    let port = free_port();
+    let _server = Server::start(
+        &[
+            "-p", &port.to_string(),
+            "--tls-cert", &certs.path("server.pem"),
+            "--tls-key", &certs.path("server.key"),
+        ],
+        port,
+    );
+
+    let addr = format!("127.0.0.1:{port}");
+    let mut client = connect_with(&addr, tls_options(&certs, false)).await.unwrap();
+
+    assert_eq!(client.set("foo", "hello").await.unwrap(), "foo");
+    assert_eq!(client.get("foo").await.unwrap(), "hello");
+
+    let untrusted = ConnectOptions {
+        tls: Some(TlsOptions { server_name: Some("localhost".to_string()), ..Default::default() }),
+        ..Default::default()
+    };
+    assert!(connect_with(&addr, untrusted).await.is_err());
+}
+
+#[tokio::test]
+async fn mutual_tls_requires_client_certificate() {
+    let certs = Certs::generate("mtls");
+    let port = free_port();
+    let _server = Server::start(
+        &[
+            "-p", &port.to_string(),
+            "--tls-cert", &certs.path("server.pem"),
+            "--tls-key", &certs.path("server.key"),
+            "--tls-ca-cert", &certs.path("ca.pem"),
+        ],
+        port,
+    );
+
+    let addr = format!("127.0.0.1:{port}");
+
+    let mut client = connect_with(&addr, tls_options(&certs, true)).await.unwrap();
+    assert_eq!(client.set("foo", "hello").await.unwrap(), "foo");
+
+    // TLS 1.3 finishes the client handshake before the server checks the certificate,
+    // so the rejection shows up on the first command.
+    if let Ok(mut anonymous) = connect_with(&addr, tls_options(&certs, false)).await {
+        assert!(anonymous.get("foo").await.is_err());
+    }
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{free_port, Server};
+use iris_client::connect_unix;
+use std::{env, fs, os::unix::fs::PermissionsExt, path::Path, thread, time::Duration};
+
+fn wait_for(path: &Path) {
+    for _ in 0..100 {
+        if path.exists() {
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    panic!("iris did not create {}", path.display());
+}
+
+#[tokio::test]
+async fn commands_over_unix_socket() {
+    let port = free_port();
+    let path = env::temp_dir().join(format!("iris-{port}.sock"));
+    let _server = Server::start(
+        &["-p", &port.to_string(), "--unix-socket", path.to_str().unwrap(), "--unix-socket-perm", "700"],
+        port,
+    );
+
+    wait_for(&path);
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o700);
+
+    let mut client = connect_unix(path.to_str().unwrap()).await.unwrap();
+    assert_eq!(client.set("foo", "hello").await.unwrap(), "foo");
+    assert_eq!(client.get("foo").await.unwrap(), "hello");
+}
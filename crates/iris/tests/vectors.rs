@@ -0,0 +1,157 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{free_port, Connection, Server};
+use iris_client::connect;
+
+#[test]
+fn add_and_get() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    assert_eq!(conn.send("VADD embedding:1 0.5 -1 2.25"), "ok OK");
+    assert_eq!(conn.send("TYPE embedding:1"), "ok vector");
+    assert_eq!(conn.send("VGET embedding:1"), "ok [0.5, -1.0, 2.25]");
+
+    assert_eq!(conn.send("VADD embedding:1 1 2"), "ok OK");
+    assert_eq!(conn.send("VGET embedding:1"), "ok [1.0, 2.0]");
+
+    assert_eq!(conn.send("VADD embedding:2 1 NaN"), r#"err Invalid vector component "NaN""#);
+    assert_eq!(conn.send("VADD embedding:2 1 x"), r#"err Invalid vector component "x""#);
+    assert_eq!(conn.send("VADD embedding:2"), r#"err "VADD" requires an ID and a Vector"#);
+    assert_eq!(conn.send("VGET missing"), "err Cannot find item with an id of missing");
+
+    conn.send("SET plain hello");
+    assert_eq!(conn.send("VGET plain"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+    assert_eq!(conn.send("GET embedding:1"), "err WRONGTYPE Operation against a key holding the wrong kind of value");
+}
+
+#[test]
+fn exact_search() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("VADD v:1 1 0");
+    conn.send("VADD v:2 0.9 0.1");
+    conn.send("VADD v:3 0 1");
+    // Strings holding JSON arrays are indexed too, and vectors of other dimensions are left out.
+    conn.send("SET v:4 [-1, 0]");
+    conn.send("VADD v:5 1 0 0");
+
+    assert_eq!(conn.send("INDEX CREATE by_angle ON v:* VECTOR 2"), "ok OK");
+    assert_eq!(conn.send("INDEX CREATE by_distance ON v:* VECTOR 2 L2"), "ok OK");
+
+    assert_eq!(conn.send("VSEARCH by_angle 2 1 0"), r#"ok ["v:1", "v:2"]"#);
+    assert_eq!(conn.send("VSEARCH by_angle 2 WITHDIST 1 0"), r#"ok [["v:1", "0.0000"], ["v:2", "0.0061"]]"#);
+    assert_eq!(conn.send("VSEARCH by_angle 10 -1 0.1"), r#"ok ["v:4", "v:3", "v:2", "v:1"]"#);
+    assert_eq!(conn.send("VSEARCH by_distance 3 0.2 0"), r#"ok ["v:2", "v:1", "v:3"]"#);
+    assert_eq!(conn.send("VSEARCH by_distance 1 WITHDIST 0.2 0"), r#"ok [["v:2", "0.7071"]]"#);
+
+    conn.send("VADD v:2 0 -1");
+    conn.send("DEL v:1");
+    assert_eq!(conn.send("VSEARCH by_angle 2 1 -0.1"), r#"ok ["v:2", "v:3"]"#);
+
+    assert_eq!(
+        conn.send("INDEX LIST"),
+        r#"ok ["by_angle ON v:* VECTOR 2 COSINE", "by_distance ON v:* VECTOR 2 L2"]"#
+    );
+}
+
+#[test]
+fn errors() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("INDEX CREATE vecs ON v:* VECTOR 2");
+    conn.send("INDEX CREATE notes ON note:* TEXT");
+
+    assert_eq!(conn.send("VSEARCH vecs 2 1 0 0"), "err Invalid vector, expected 2 dimensions, got 3");
+    assert_eq!(conn.send("VSEARCH vecs 2 0 0"), "err Invalid vector, a zero vector has no direction to compare by cosine");
+    assert_eq!(conn.send("VSEARCH vecs two 1 0"), r#"err Invalid count "two""#);
+    assert_eq!(conn.send("VSEARCH vecs 2 EXACT"), r#"err "VSEARCH" requires an Index, a Count and a Vector"#);
+    assert_eq!(conn.send("VSEARCH notes 2 1 0"), "err Index notes isn't a vector index, use SEARCH");
+    assert_eq!(conn.send("FIND vecs 1"), "err Index vecs is a vector index, use VSEARCH");
+
+    // Asking for more than there are returns every key, approximate or not.
+    conn.send("INDEX CREATE graph ON v:* VECTOR 2 HNSW");
+    conn.send("VADD v:1 1 0");
+    conn.send("VADD v:2 0 1");
+    assert_eq!(conn.send("VSEARCH graph 18446744073709551615 1 0"), r#"ok ["v:1", "v:2"]"#);
+    assert_eq!(conn.send("VSEARCH vecs 18446744073709551615 1 0"), r#"ok ["v:1", "v:2"]"#);
+
+    assert_eq!(conn.send("INDEX CREATE bad ON v:* VECTOR 0"), r#"err Invalid dimensions "0""#);
+    assert_eq!(
+        conn.send("INDEX CREATE bad ON v:* VECTOR 2 DOT"),
+        r#"err Invalid metric "DOT", valid values: ('COSINE', 'L2')"#
+    );
+}
+
+/// A pseudo random vector, the same for the same seed.
+fn random(seed: &mut u64, dimensions: usize) -> Vec<String> {
+    (0..dimensions)
+        .map(|_| {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            format!("{:.4}", (*seed >> 33) as f64 / (1u64 << 31) as f64 * 2.0 - 1.0)
+        })
+        .collect()
+}
+
+#[test]
+fn approximate_search_finds_most_of_the_nearest() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut conn = Connection::open(port);
+
+    conn.send("INDEX CREATE embeddings ON e:* VECTOR 16 HNSW");
+    conn.send("INDEX CREATE distances ON e:* VECTOR 16 L2 HNSW");
+
+    let mut seed = 42;
+    for i in 0..1000 {
+        conn.send(&format!("VADD e:{i} {}", random(&mut seed, 16).join(" ")));
+    }
+
+    // Removing most of them rebuilds the graph without them.
+    for i in 200..800 {
+        conn.send(&format!("DEL e:{i}"));
+    }
+
+    let ids = |reply: String| -> HashSet<String> {
+        let reply = reply.strip_prefix("ok [").unwrap().strip_suffix(']').unwrap().to_string();
+        reply.split(", ").map(|id| id.trim_matches('"').to_string()).collect()
+    };
+
+    for index in ["embeddings", "distances"] {
+        let (mut found, mut total) = (0, 0);
+
+        for _ in 0..20 {
+            let query = random(&mut seed, 16).join(" ");
+            let approximate = ids(conn.send(&format!("VSEARCH {index} 10 {query}")));
+            let exact = ids(conn.send(&format!("VSEARCH {index} 10 EXACT {query}")));
+
+            assert!(exact.iter().all(|id| id[2..].parse::<usize>().is_ok_and(|i| !(200..800).contains(&i))));
+            found += approximate.intersection(&exact).count();
+            total += exact.len();
+        }
+
+        assert_eq!(total, 200);
+        assert!(found * 100 >= total * 90, "{index} found {found} of {total}");
+    }
+}
+
+#[tokio::test]
+async fn client() {
+    let port = free_port();
+    let _server = Server::start(&["-p", &port.to_string()], port);
+    let mut client = connect(&format!("127.0.0.1:{port}")).await.unwrap();
+
+    client.index_create_vector("embeddings", "doc:*", 3, "COSINE", false).await.unwrap();
+    client.vadd("doc:1", &[1.0, 0.0, 0.0]).await.unwrap();
+    client.vadd("doc:2", &[0.0, 1.0, 0.5]).await.unwrap();
+
+    assert_eq!(client.vget("doc:2").await.unwrap(), vec![0.0, 1.0, 0.5]);
+    assert_eq!(client.vsearch("embeddings", 1, &[0.9, 0.1, 0.0]).await.unwrap(), vec!["doc:1"]);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{free_port, Server};
+use futures_util::{SinkExt, StreamExt};
+use std::{net::TcpListener, process::Command};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn send(socket: &mut Socket, command: &str) -> String {
+    socket.send(Message::text(command)).await.unwrap();
+    next_text(socket).await
+}
+
+async fn next_text(socket: &mut Socket) -> String {
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => return text.to_string(),
+            Some(Ok(_)) => continue,
+            other => panic!("unexpected websocket message: {other:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn commands_over_websocket() {
+    let (port, ws_port) = (free_port(), free_port());
+    let _server = Server::start(&["-p", &port.to_string(), "--ws-port", &ws_port.to_string()], ws_port);
+
+    let (mut socket, _) = connect_async(format!("ws://127.0.0.1:{ws_port}/")).await.unwrap();
+
+    assert_eq!(send(&mut socket, "PING").await, "ok PONG");
+    assert_eq!(send(&mut socket, "SET foo hello world").await, "ok foo");
+    assert_eq!(send(&mut socket, "GET foo").await, "ok hello world");
+    assert_eq!(send(&mut socket, "SET bar baz ~> GET").await, "ok baz");
+    assert_eq!(send(&mut socket, "GET nope").await, "err Cannot find item with an id of nope");
+}
+
+#[tokio::test]
+async fn subscriptions_push_events() {
+    let (port, ws_port) = (free_port(), free_port());
+    let _server = Server::start(&["-p", &port.to_string(), "--ws-port", &ws_port.to_string()], ws_port);
+
+    let url = format!("ws://127.0.0.1:{ws_port}/");
+    let (mut subscriber, _) = connect_async(&url).await.unwrap();
+    let (mut writer, _) = connect_async(&url).await.unwrap();
+
+    assert_eq!(send(&mut subscriber, "SUB user:*").await, "ok user:*");
+
+    assert_eq!(send(&mut writer, "SET other 1").await, "ok other");
+    assert_eq!(send(&mut writer, "SET user:joe 1").await, "ok user:joe");
+    assert_eq!(send(&mut writer, "DEL user:joe").await, "ok 1");
+
+    assert_eq!(next_text(&mut subscriber).await, "event set user:joe");
+    assert_eq!(next_text(&mut subscriber).await, "event del user:joe");
+
+    assert_eq!(send(&mut subscriber, "UNSUB user:*").await, "ok user:*");
+    assert_eq!(send(&mut writer, "SET user:ann 1").await, "ok user:ann");
+    assert_eq!(send(&mut subscriber, "PING").await, "ok PONG");
+}
+
+#[test]
+fn taken_port_stops_the_server() {
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ws_port = taken.local_addr().unwrap().port();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_iris"))
+        .args(["server", "-p", &free_port().to_string(), "--ws-port", &ws_port.to_string()])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Failed to start the websocket server"));
+}
+
+#[tokio::test]
+async fn geoadd_only_pushes_changes() {
+    let (port, ws_port) = (free_port(), free_port());
+    let _server = Server::start(&["-p", &port.to_string(), "--ws-port", &ws_port.to_string()], ws_port);
+
+    let url = format!("ws://127.0.0.1:{ws_port}/");
+    let (mut subscriber, _) = connect_async(&url).await.unwrap();
+    let (mut writer, _) = connect_async(&url).await.unwrap();
+
+    assert_eq!(send(&mut subscriber, "SUB places*").await, "ok places*");
+
+    assert_eq!(send(&mut writer, "GEOADD places 13.361389 38.115556 Palermo").await, "ok 1");
+    assert_eq!(send(&mut writer, "GEOADD places 13.361389 38.115556 Palermo").await, "ok 0");
+    assert_eq!(send(&mut writer, "GEOADD places 15.087269 37.502669 Palermo").await, "ok 0");
+    assert_eq!(send(&mut writer, "SET places:done 1").await, "ok places:done");
+
+    // Adding Palermo again where it already was changed nothing, so only the add and the move are pushed.
+    assert_eq!(next_text(&mut subscriber).await, "event set places");
+    assert_eq!(next_text(&mut subscriber).await, "event set places");
+    assert_eq!(next_text(&mut subscriber).await, "event set places:done");
+}
@@ -1,11 +1,18 @@
 mod pipe_builder;
 
-use std::ops::Range;
+use std::{fmt, ops::Range, sync::Arc};
 use pipe_builder::PipeBuilder;
 use regex::Regex;
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpStream, UnixStream},
+};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
 };
 
 macro_rules! send_command {
@@ -30,32 +37,122 @@ pub enum DeleteExpression<'a> {
     Range(Range<i32>)
 }
 
+/// How `bitop` combines its sources.
+#[derive(Debug, Clone, Copy)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl fmt::Display for BitOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitOp::And => write!(f, "AND"),
+            BitOp::Or => write!(f, "OR"),
+            BitOp::Xor => write!(f, "XOR"),
+            BitOp::Not => write!(f, "NOT"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ServerResponse {
     pub status: String,
     pub data: String,
 }
 
+/// Where `geosearch` searches around.
+#[derive(Debug, Clone, Copy)]
+pub enum GeoCenter<'a> {
+    Member(&'a str),
+    LonLat(f64, f64),
+}
+
+/// The area `geosearch` searches in, with a unit of `m`, `km`, `ft` or `mi`.
+#[derive(Debug, Clone, Copy)]
+pub enum GeoShape<'a> {
+    Radius(f64, &'a str),
+    Box { width: f64, height: f64, unit: &'a str },
+}
+
+/// An entry of a stream and its field value pairs.
+#[derive(Debug)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: Vec<Item>,
+}
+
 #[derive(Debug)]
 pub struct Item {
     pub id: String,
     pub data: String
 }
 
-#[derive(Debug)]
+/// Options applied when opening a connection with [`connect_with`].
+#[derive(Debug, Default, Clone)]
+pub struct ConnectOptions {
+    /// Sent with `AUTH` right after connecting, for servers started with `--requirepass`.
+    pub password: Option<String>,
+    /// The acl user to `AUTH` as. Uses the server's default user when `None`.
+    pub username: Option<String>,
+    /// Connect over tls, for servers started with `--tls-cert`.
+    pub tls: Option<TlsOptions>,
+    /// The database to `SELECT` right after connecting. The server starts connections on `0`.
+    pub database: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TlsOptions {
+    /// PEM ca to verify the server with. Uses the webpki roots when `None`.
+    pub ca_cert: Option<String>,
+    /// PEM certificate and key presented to servers started with `--tls-ca-cert`.
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    /// The name the server certificate must be valid for. Defaults to the host of the address.
+    pub server_name: Option<String>,
+}
+
+trait Socket: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Socket for T {}
+
 pub struct IrisClient {
-    socket: TcpStream,
+    socket: Box<dyn Socket>,
+}
+
+impl fmt::Debug for IrisClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IrisClient").finish_non_exhaustive()
+    }
 }
 
 impl IrisClient {
-    pub async fn set(self: &mut Self, id: &str, data: &str) -> Result<String, String> {
+    async fn handshake(socket: Box<dyn Socket>, options: &ConnectOptions) -> Result<IrisClient, String> {
+        let mut client = IrisClient { socket };
+
+        match (&options.username, &options.password) {
+            (Some(username), Some(password)) => client.auth_user(username, password).await?,
+            (None, Some(password)) => client.auth(password).await?,
+            _ => {}
+        }
+
+        if let Some(database) = options.database {
+            client.select(database).await?;
+        }
+
+        Ok(client)
+    }
+
+    pub async fn set(&mut self, id: &str, data: &str) -> Result<String, String> {
         send_command!(self.socket, format!("SET {id} {data}\n"));
 
         let server_resp = self.server_response().await?;
         Ok(server_resp.data)
     }
 
-    pub async fn delete<'a>(self: &mut Self, expr: DeleteExpression<'a>) -> Result<Vec<Item>, String> {
+    pub async fn delete<'a>(&mut self, expr: DeleteExpression<'a>) -> Result<Vec<Item>, String> {
         match expr {
             DeleteExpression::Number(count) => send_command!(self.socket, format!("DEL {count}\n")),
             DeleteExpression::ID(id) => send_command!(self.socket, format!("DEL {id}\n")),
@@ -68,14 +165,395 @@ impl IrisClient {
         Ok(deleted)
     }
 
-    pub async fn get(self: &mut Self, id: &str) -> Result<String, String> {
+    pub async fn get(&mut self, id: &str) -> Result<String, String> {
         send_command!(self.socket, format!("GET {id}\n"));
 
         let server_resp = self.server_response().await?;
         Ok(server_resp.data)
     }
 
-    pub async fn list(self: &mut Self, expr: Expression) -> Result<Vec<Item>, String> {
+    /// Appends to a value, creating it when missing. Returns the new length in bytes.
+    pub async fn append(&mut self, id: &str, data: &str) -> Result<usize, String> {
+        send_command!(self.socket, format!("APPEND {id} {data}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// The length of a value in bytes, `0` when it's missing.
+    pub async fn strlen(&mut self, id: &str) -> Result<usize, String> {
+        send_command!(self.socket, format!("STRLEN {id}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// The bytes from `start` to `end` inclusive, negative offsets count from the end.
+    pub async fn getrange(&mut self, id: &str, start: i32, end: i32) -> Result<String, String> {
+        send_command!(self.socket, format!("GETRANGE {id} {start}..{end}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// Overwrites part of a value starting at `offset`. Returns the new length in bytes.
+    pub async fn setrange(&mut self, id: &str, offset: usize, data: &str) -> Result<usize, String> {
+        send_command!(self.socket, format!("SETRANGE {id} {offset} {data}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Sets the bit at `offset`, growing the bitmap as needed. Returns the bit it replaced.
+    pub async fn setbit(&mut self, id: &str, offset: usize, bit: bool) -> Result<bool, String> {
+        send_command!(self.socket, format!("SETBIT {id} {offset} {}\n", bit as u8));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// The bit at `offset`, `false` past the end or when the value is missing.
+    pub async fn getbit(&mut self, id: &str, offset: usize) -> Result<bool, String> {
+        send_command!(self.socket, format!("GETBIT {id} {offset}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Counts the set bits, of the whole value or of a byte range like `0..1`.
+    pub async fn bitcount(&mut self, id: &str, range: Option<Range<i32>>) -> Result<usize, String> {
+        match range {
+            Some(range) => send_command!(self.socket, format!("BITCOUNT {id} {:?}\n", range)),
+            None => send_command!(self.socket, format!("BITCOUNT {id}\n")),
+        }
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Combines `sources` into `destination`. Returns the length of the result in bytes.
+    pub async fn bitop(&mut self, op: BitOp, destination: &str, sources: &[&str]) -> Result<usize, String> {
+        send_command!(self.socket, format!("BITOP {op} {destination} {}\n", sources.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Adds elements to a HyperLogLog, creating it when missing. Returns whether the estimate changed.
+    pub async fn pfadd(&mut self, id: &str, elements: &[&str]) -> Result<bool, String> {
+        send_command!(self.socket, format!("PFADD {id} {}\n", elements.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Estimates how many distinct elements were added to the HyperLogLogs together.
+    pub async fn pfcount(&mut self, ids: &[&str]) -> Result<usize, String> {
+        send_command!(self.socket, format!("PFCOUNT {}\n", ids.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Merges HyperLogLogs into `destination`, keeping what it already had.
+    pub async fn pfmerge(&mut self, destination: &str, sources: &[&str]) -> Result<(), String> {
+        send_command!(self.socket, format!("PFMERGE {destination} {}\n", sources.join(" ")));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Appends an entry to a stream, creating it when missing. Returns the id it got.
+    pub async fn xadd(&mut self, id: &str, fields: &[(&str, &str)]) -> Result<String, String> {
+        let fields: Vec<String> = fields.iter().map(|(field, value)| format!("{field} {value}")).collect();
+        send_command!(self.socket, format!("XADD {id} * {}\n", fields.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// The entries from `start` to `end` inclusive, `-` and `+` for the first and last.
+    pub async fn xrange(&mut self, id: &str, start: &str, end: &str, count: Option<usize>) -> Result<Vec<StreamEntry>, String> {
+        match count {
+            Some(count) => send_command!(self.socket, format!("XRANGE {id} {start} {end} COUNT {count}\n")),
+            None => send_command!(self.socket, format!("XRANGE {id} {start} {end}\n")),
+        }
+
+        let server_resp = self.server_response().await?;
+        self.parse_stream(&server_resp.data)
+    }
+
+    pub async fn xlen(&mut self, id: &str) -> Result<usize, String> {
+        send_command!(self.socket, format!("XLEN {id}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Drops the oldest entries until at most `maxlen` are left. Returns how many were dropped.
+    pub async fn xtrim(&mut self, id: &str, maxlen: usize) -> Result<usize, String> {
+        send_command!(self.socket, format!("XTRIM {id} MAXLEN {maxlen}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Creates a consumer group reading after `start`, `$` for only new entries.
+    pub async fn xgroup_create(&mut self, id: &str, group: &str, start: &str, mkstream: bool) -> Result<(), String> {
+        match mkstream {
+            true => send_command!(self.socket, format!("XGROUP CREATE {id} {group} {start} MKSTREAM\n")),
+            false => send_command!(self.socket, format!("XGROUP CREATE {id} {group} {start}\n")),
+        }
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Reads as `consumer` of a group. `after` is `>` for entries no one in the group got yet,
+    /// or an id to get the consumer's own unacknowledged entries after it again.
+    pub async fn xreadgroup(
+        &mut self,
+        id: &str,
+        group: &str,
+        consumer: &str,
+        after: &str,
+        count: Option<usize>,
+    ) -> Result<Vec<StreamEntry>, String> {
+        match count {
+            Some(count) => send_command!(self.socket, format!("XREADGROUP GROUP {group} {consumer} COUNT {count} STREAMS {id} {after}\n")),
+            None => send_command!(self.socket, format!("XREADGROUP GROUP {group} {consumer} STREAMS {id} {after}\n")),
+        }
+
+        let server_resp = self.server_response().await?;
+        self.parse_stream(&server_resp.data)
+    }
+
+    /// Acknowledges entries of a group. Returns how many were pending.
+    pub async fn xack(&mut self, id: &str, group: &str, entries: &[&str]) -> Result<usize, String> {
+        send_command!(self.socket, format!("XACK {id} {group} {}\n", entries.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Adds or moves members to a longitude and latitude. Returns how many are new.
+    pub async fn geoadd(&mut self, id: &str, members: &[(f64, f64, &str)]) -> Result<usize, String> {
+        let members: Vec<String> = members.iter().map(|(lon, lat, member)| format!("{lon} {lat} {member}")).collect();
+        send_command!(self.socket, format!("GEOADD {id} {}\n", members.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// The longitude and latitude of members, `None` for the missing ones.
+    pub async fn geopos(&mut self, id: &str, members: &[&str]) -> Result<Vec<Option<(f64, f64)>>, String> {
+        send_command!(self.socket, format!("GEOPOS {id} {}\n", members.join(" ")));
+
+        let server_resp = self.server_response().await?;
+
+        self.parse_list(&server_resp.data)?
+            .into_iter()
+            .map(|position| match position.split_once(' ') {
+                Some((lon, lat)) => match (lon.parse(), lat.parse()) {
+                    (Ok(lon), Ok(lat)) => Ok(Some((lon, lat))),
+                    _ => Err("Invalid position response".to_string()),
+                },
+                None if position == "nil" => Ok(None),
+                None => Err("Invalid position response".to_string()),
+            })
+            .collect()
+    }
+
+    /// The distance between two members in `unit`, `m`, `km`, `ft` or `mi`.
+    pub async fn geodist(&mut self, id: &str, from: &str, to: &str, unit: &str) -> Result<f64, String> {
+        send_command!(self.socket, format!("GEODIST {id} {from} {to} {unit}\n"));
+
+        let server_resp = self.server_response().await?;
+        server_resp.data.parse::<f64>().map_err(|_| "Invalid distance response".to_string())
+    }
+
+    /// The members in `shape` around `center`, closest first.
+    pub async fn geosearch(&mut self, id: &str, center: GeoCenter<'_>, shape: GeoShape<'_>) -> Result<Vec<String>, String> {
+        let center = match center {
+            GeoCenter::Member(member) => format!("FROMMEMBER {member}"),
+            GeoCenter::LonLat(lon, lat) => format!("FROMLONLAT {lon} {lat}"),
+        };
+
+        let shape = match shape {
+            GeoShape::Radius(radius, unit) => format!("BYRADIUS {radius} {unit}"),
+            GeoShape::Box { width, height, unit } => format!("BYBOX {width} {height} {unit}"),
+        };
+
+        send_command!(self.socket, format!("GEOSEARCH {id} {center} {shape}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(&server_resp.data)
+    }
+
+    /// Sets the JSON `value` at `path`, `$` for the whole document.
+    pub async fn jset(&mut self, id: &str, path: &str, value: &str) -> Result<(), String> {
+        send_command!(self.socket, format!("JSET {id} {path} {value}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// The JSON at `path`, serialized.
+    pub async fn jget(&mut self, id: &str, path: &str) -> Result<String, String> {
+        send_command!(self.socket, format!("JGET {id} {path}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// Removes the JSON at `path`, the whole key for `$`. Returns whether there was any.
+    pub async fn jdel(&mut self, id: &str, path: &str) -> Result<bool, String> {
+        send_command!(self.socket, format!("JDEL {id} {path}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Appends JSON values to the array at `path`. Returns its new length.
+    pub async fn jarrappend(&mut self, id: &str, path: &str, values: &[&str]) -> Result<usize, String> {
+        send_command!(self.socket, format!("JARRAPPEND {id} {path} {}\n", values.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_number(&server_resp.data)
+    }
+
+    /// Adds to the number at `path`. Returns the new number.
+    pub async fn jnumincrby(&mut self, id: &str, path: &str, by: f64) -> Result<f64, String> {
+        send_command!(self.socket, format!("JNUMINCRBY {id} {path} {by}\n"));
+
+        let server_resp = self.server_response().await?;
+        server_resp.data.parse::<f64>().map_err(|_| "Invalid number response".to_string())
+    }
+
+    /// Indexes the keys matching `pattern` by the JSON value at `path`, like `$.city`.
+    pub async fn index_create(&mut self, name: &str, pattern: &str, path: &str) -> Result<(), String> {
+        send_command!(self.socket, format!("INDEX CREATE {name} ON {pattern} FIELD {path}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Indexes the words of the keys matching `pattern` for `search`, or of the part of JSON documents at `path`.
+    pub async fn index_create_text(&mut self, name: &str, pattern: &str, path: Option<&str>) -> Result<(), String> {
+        match path {
+            Some(path) => send_command!(self.socket, format!("INDEX CREATE {name} ON {pattern} TEXT FIELD {path}\n")),
+            None => send_command!(self.socket, format!("INDEX CREATE {name} ON {pattern} TEXT\n")),
+        }
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Indexes the vectors of the keys matching `pattern` for `vsearch`, by `COSINE` or `L2` distance.
+    /// `approximate` searches through a HNSW graph instead of comparing against every vector.
+    pub async fn index_create_vector(
+        &mut self,
+        name: &str,
+        pattern: &str,
+        dimensions: usize,
+        metric: &str,
+        approximate: bool,
+    ) -> Result<(), String> {
+        let hnsw = if approximate { " HNSW" } else { "" };
+        send_command!(self.socket, format!("INDEX CREATE {name} ON {pattern} VECTOR {dimensions} {metric}{hnsw}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Removes an index. Returns whether there was one.
+    pub async fn index_drop(&mut self, name: &str) -> Result<bool, String> {
+        send_command!(self.socket, format!("INDEX DROP {name}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Every index as `<name> ON <pattern> FIELD <path>`.
+    pub async fn index_list(&mut self) -> Result<Vec<String>, String> {
+        send_command!(self.socket, "INDEX LIST\n".to_string());
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(&server_resp.data)
+    }
+
+    /// The ids whose indexed value is `query`, a value or an inclusive `<min>..<max>` range.
+    pub async fn find(&mut self, name: &str, query: &str) -> Result<Vec<String>, String> {
+        send_command!(self.socket, format!("FIND {name} {query}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(&server_resp.data)
+    }
+
+    /// The ids matching a text `query`, most relevant first. Words all have to match, `OR` takes either side,
+    /// `NOT` or `-` leaves matches out and `word*` matches prefixes.
+    pub async fn search(&mut self, name: &str, query: &str) -> Result<Vec<String>, String> {
+        send_command!(self.socket, format!("SEARCH {name} {query}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(&server_resp.data)
+    }
+
+    /// Stores a vector, replacing whatever the key held.
+    pub async fn vadd(&mut self, id: &str, vector: &[f32]) -> Result<(), String> {
+        let components: Vec<String> = vector.iter().map(|component| component.to_string()).collect();
+        send_command!(self.socket, format!("VADD {id} {}\n", components.join(" ")));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    pub async fn vget(&mut self, id: &str) -> Result<Vec<f32>, String> {
+        send_command!(self.socket, format!("VGET {id}\n"));
+
+        let server_resp = self.server_response().await?;
+        server_resp
+            .data
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(", ")
+            .map(|component| component.parse::<f32>().map_err(|_| "Invalid vector response".to_string()))
+            .collect()
+    }
+
+    /// The ids of the `k` nearest vectors to `vector`, nearest first.
+    pub async fn vsearch(&mut self, name: &str, k: usize, vector: &[f32]) -> Result<Vec<String>, String> {
+        let components: Vec<String> = vector.iter().map(|component| component.to_string()).collect();
+        send_command!(self.socket, format!("VSEARCH {name} {k} {}\n", components.join(" ")));
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(&server_resp.data)
+    }
+
+    /// Runs a script in braces atomically, with `KEYS` and `ARGS` in scope. Returns what it evaluates to.
+    pub async fn eval(&mut self, script: &str, keys: &[&str], args: &[&str]) -> Result<String, String> {
+        send_command!(self.socket, format!("EVAL {script} {} {}\n", keys.len(), [keys, args].concat().join(" ")));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// Compiles a script without running it, returning the sha to run it with `evalsha`.
+    pub async fn script_load(&mut self, script: &str) -> Result<String, String> {
+        send_command!(self.socket, format!("SCRIPT LOAD {script}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    pub async fn evalsha(&mut self, sha: &str, keys: &[&str], args: &[&str]) -> Result<String, String> {
+        send_command!(self.socket, format!("EVALSHA {sha} {} {}\n", keys.len(), [keys, args].concat().join(" ")));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    pub async fn list(&mut self, expr: Expression) -> Result<Vec<Item>, String> {
         match expr {
             Expression::Number(count) => send_command!(self.socket, format!("LST {count}\n")),
             Expression::Range(range) => send_command!(self.socket, format!("LST {:?}\n", range))
@@ -87,7 +565,7 @@ impl IrisClient {
         Ok(list)
     }
 
-    pub async fn count(self: &mut Self, expr: Expression) -> Result<u32, String> {
+    pub async fn count(&mut self, expr: Expression) -> Result<u32, String> {
         match expr {
             Expression::Number(count) => send_command!(self.socket, format!("CNT {count}\n")),
             Expression::Range(range) => send_command!(self.socket, format!("CNT {:?}\n", range))
@@ -99,21 +577,128 @@ impl IrisClient {
         Ok(count)
     }
 
-    pub async fn raw(self: &mut Self, command: &str) -> Result<ServerResponse, String> {
+    pub async fn auth(&mut self, password: &str) -> Result<(), String> {
+        send_command!(self.socket, format!("AUTH {password}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    pub async fn auth_user(&mut self, username: &str, password: &str) -> Result<(), String> {
+        send_command!(self.socket, format!("AUTH {username} {password}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    pub async fn whoami(&mut self) -> Result<String, String> {
+        send_command!(self.socket, "ACL WHOAMI\n".to_string());
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// Whether a value is stored under the id.
+    pub async fn exists(&mut self, id: &str) -> Result<bool, String> {
+        send_command!(self.socket, format!("EXISTS {id}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Moves a value to a new id, replacing whatever was there.
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<(), String> {
+        send_command!(self.socket, format!("RENAME {from} {to}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Moves a value to a new id unless it's taken. Returns whether it was moved.
+    pub async fn rename_nx(&mut self, from: &str, to: &str) -> Result<bool, String> {
+        send_command!(self.socket, format!("RENAMENX {from} {to}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// Copies a value to another id. Without `replace` an existing id is left alone. Returns whether it was copied.
+    pub async fn copy(&mut self, from: &str, to: &str, replace: bool) -> Result<bool, String> {
+        match replace {
+            true => send_command!(self.socket, format!("COPY {from} {to} REPLACE\n")),
+            false => send_command!(self.socket, format!("COPY {from} {to}\n")),
+        }
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data == "1")
+    }
+
+    /// The type of the value, `none` if the id doesn't exist.
+    pub async fn key_type(&mut self, id: &str) -> Result<String, String> {
+        send_command!(self.socket, format!("TYPE {id}\n"));
+
+        let server_resp = self.server_response().await?;
+        Ok(server_resp.data)
+    }
+
+    /// Lists ids without their values.
+    pub async fn keys(&mut self, expr: Expression) -> Result<Vec<String>, String> {
+        match expr {
+            Expression::Number(count) => send_command!(self.socket, format!("KEYS {count}\n")),
+            Expression::Range(range) => send_command!(self.socket, format!("KEYS {:?}\n", range))
+        }
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(server_resp.data.as_str())
+    }
+
+    /// Lists the ids matching a glob pattern like `user:*`.
+    pub async fn keys_matching(&mut self, pattern: &str) -> Result<Vec<String>, String> {
+        send_command!(self.socket, format!("KEYS {pattern}\n"));
+
+        let server_resp = self.server_response().await?;
+        self.parse_list(server_resp.data.as_str())
+    }
+
+    /// Switches the connection to another database.
+    pub async fn select(&mut self, database: usize) -> Result<(), String> {
+        send_command!(self.socket, format!("SELECT {database}\n"));
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Deletes every key of the selected database.
+    pub async fn flushdb(&mut self) -> Result<(), String> {
+        send_command!(self.socket, "FLUSHDB\n".to_string());
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    /// Deletes every key of every database.
+    pub async fn flushall(&mut self) -> Result<(), String> {
+        send_command!(self.socket, "FLUSHALL\n".to_string());
+
+        self.server_response().await?;
+        Ok(())
+    }
+
+    pub async fn raw(&mut self, command: &str) -> Result<ServerResponse, String> {
         send_command!(self.socket, format!("{command}\n"));
 
         let server_resp = self.server_response().await?;
         Ok(server_resp)
     }
 
-    pub fn pipe(self: &mut Self) -> PipeBuilder {
+    pub fn pipe(&mut self) -> PipeBuilder<'_> {
         PipeBuilder {
             command: String::new(),
             client: self
         }
     }
 
-    async fn server_response(self: &mut Self) -> Result<ServerResponse, String> {
+    async fn server_response(&mut self) -> Result<ServerResponse, String> {
         let mut buf_reader = BufReader::new(&mut self.socket);
         let mut buffer = String::new();
         let server_resp = match buf_reader.read_line(&mut buffer).await {
@@ -137,11 +722,51 @@ impl IrisClient {
         let parts: Vec<&str> = response.splitn(2, ' ').collect();
 
         ServerResponse {
-            status: parts.get(0).unwrap().to_string(),
-            data: parts.get(1).unwrap().to_string(),
+            status: parts.first().unwrap().to_string(),
+            // Empty values like a `GETRANGE` past the end come back as a bare status.
+            data: parts.get(1).copied().unwrap_or_default().to_string(),
         }
     }
 
+    fn parse_number(&self, response: &str) -> Result<usize, String> {
+        response.parse::<usize>().map_err(|_| "Invalid number response".to_string())
+    }
+
+    fn parse_list(&self, response: &str) -> Result<Vec<String>, String> {
+        let regex = Regex::new(r#"^\s*\[\s*(".*?"\s*,?\s*)*\]\s*$"#).unwrap();
+
+        if !regex.is_match(response) {
+            return Err("Invalid list response".to_string());
+        }
+
+        let items = Regex::new(r#""(.*?)""#).unwrap();
+
+        Ok(items
+            .captures_iter(response)
+            .map(|cap| cap.get(1).unwrap().as_str().to_string())
+            .collect())
+    }
+
+    fn parse_stream(&self, response: &str) -> Result<Vec<StreamEntry>, String> {
+        let regex = Regex::new(r#"^\s*\[\s*(\("[^"]*",\s*\[.*?\]\)\s*,?\s*)*\]\s*$"#).unwrap();
+
+        if !regex.is_match(response) {
+            return Err("Invalid stream response".to_string());
+        }
+
+        let entries = Regex::new(r#"\("([^"]*)",\s*(\[.*?\])\)"#).unwrap();
+
+        entries
+            .captures_iter(response)
+            .map(|cap| {
+                Ok(StreamEntry {
+                    id: cap.get(1).unwrap().as_str().to_string(),
+                    fields: self.parse_tuple(cap.get(2).unwrap().as_str())?,
+                })
+            })
+            .collect()
+    }
+
     fn parse_tuple(&self, response: &str) -> Result<Vec<Item>, String> {
         let regex = Regex::new(r#"\s*\[\s*(\(".*?",\s*".*?"\)\s*,?\s*)*\]\s*"#).unwrap();
 
@@ -167,9 +792,83 @@ impl IrisClient {
 }
 
 pub async fn connect(addr: &str) -> Result<IrisClient, String> {
+    connect_with(addr, ConnectOptions::default()).await
+}
+
+pub async fn connect_with(addr: &str, options: ConnectOptions) -> Result<IrisClient, String> {
     let socket = TcpStream::connect(addr)
         .await
         .map_err(|err| format!("Failed to connect: {err}"))?;
 
-    Ok(IrisClient { socket })
+    let socket: Box<dyn Socket> = match &options.tls {
+        Some(tls) => {
+            let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+            let server_name = ServerName::try_from(tls.server_name.as_deref().unwrap_or(host).to_string())
+                .map_err(|err| format!("Invalid tls server name: {err}"))?;
+
+            let stream = tls_connector(tls)?
+                .connect(server_name, socket)
+                .await
+                .map_err(|err| format!("Failed the tls handshake: {err}"))?;
+
+            Box::new(stream)
+        }
+        None => Box::new(socket),
+    };
+
+    IrisClient::handshake(socket, &options).await
+}
+
+/// Connects over a unix socket, for servers started with `--unix-socket`.
+pub async fn connect_unix(path: &str) -> Result<IrisClient, String> {
+    connect_unix_with(path, ConnectOptions::default()).await
+}
+
+pub async fn connect_unix_with(path: &str, options: ConnectOptions) -> Result<IrisClient, String> {
+    if options.tls.is_some() {
+        return Err("TLS is not supported over unix sockets".to_string());
+    }
+
+    let socket = UnixStream::connect(path)
+        .await
+        .map_err(|err| format!("Failed to connect: {err}"))?;
+
+    IrisClient::handshake(Box::new(socket), &options).await
+}
+
+fn tls_connector(options: &TlsOptions) -> Result<TlsConnector, String> {
+    let roots = match &options.ca_cert {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+
+            for cert in load_certs(path)? {
+                roots.add(cert).map_err(|err| format!("Invalid ca certificate: {err}"))?;
+            }
+
+            roots
+        }
+        None => RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() },
+    };
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+
+    let config = match (&options.client_cert, &options.client_key) {
+        (Some(cert), Some(key)) => {
+            let key = PrivateKeyDer::from_pem_file(key)
+                .map_err(|err| format!("Failed to read the client key: {err}"))?;
+
+            builder
+                .with_client_auth_cert(load_certs(cert)?, key)
+                .map_err(|err| format!("Invalid client certificate or key: {err}"))?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| format!("Failed to read certificates from {path}: {err}"))
 }
@@ -6,7 +6,7 @@ pub struct PipeBuilder<'a> {
 }
 
 impl<'a> PipeBuilder<'a> {
-    pub fn set(self: &'a mut Self, id: &str, data: &str) -> Self {
+    pub fn set(&'a mut self, id: &str, data: &str) -> Self {
         self.command.push_str(format!("SET {id} {data} ~>").as_str());
 
         Self {
@@ -15,7 +15,7 @@ impl<'a> PipeBuilder<'a> {
         }
     }
 
-    pub fn get(self: &'a mut Self, id: &str) -> Self {
+    pub fn get(&'a mut self, id: &str) -> Self {
         self.command.push_str(format!(" GET {id}").as_str());
 
         Self {
@@ -24,7 +24,7 @@ impl<'a> PipeBuilder<'a> {
         }
     }
 
-    pub async fn execute(self: &mut Self) -> Result<ServerResponse, String> {
+    pub async fn execute(&mut self) -> Result<ServerResponse, String> {
         let server_resp = self.client.raw(self.command.trim()).await?;
         Ok(server_resp)
     }